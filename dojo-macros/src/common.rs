@@ -1,27 +1,74 @@
 use proc_macro2::Ident;
-use syn::{Fields, GenericArgument, Type};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, FieldsNamed, GenericArgument, Type};
 
-pub fn derive_get_fields_with_tys(fields: Fields) -> Vec<(Ident, Ident, Option<GenericArgument>)> {
+pub fn named_fields(ast: &DeriveInput, derive: &str) -> syn::Result<FieldsNamed> {
+    match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.clone()),
+            fields => Err(syn::Error::new(
+                fields.span(),
+                format!(
+                    "{} can only be derived for structs with named fields",
+                    derive
+                ),
+            )),
+        },
+        Data::Enum(data) => Err(syn::Error::new(
+            data.enum_token.span,
+            format!("{} can only be derived for structs", derive),
+        )),
+        Data::Union(data) => Err(syn::Error::new(
+            data.union_token.span,
+            format!("{} can only be derived for structs", derive),
+        )),
+    }
+}
+
+pub fn reject_generics(ast: &DeriveInput, derive: &str) -> syn::Result<()> {
+    if ast.generics.params.is_empty() {
+        return Ok(());
+    }
+
+    Err(syn::Error::new(
+        ast.generics.span(),
+        format!("{} can't be derived for generic types", derive),
+    ))
+}
+
+pub fn derive_get_fields_with_tys(
+    fields: Fields,
+) -> syn::Result<Vec<(Ident, Ident, Option<GenericArgument>)>> {
     fields
         .into_iter()
         .filter_map(|f| match f.ty {
-            Type::Path(tp) => Some((f.ident.unwrap(), tp)),
+            Type::Path(tp) => Some((f.ident, tp)),
             _ => None,
         })
         .map(|(ident, tp)| {
-            let outer_ty = tp.path.segments.first().unwrap().ident.clone();
+            let ident = ident.ok_or_else(|| syn::Error::new(tp.span(), "field must be named"))?;
+            let outer_ty = match tp.path.segments.first() {
+                Some(segment) => segment.ident.clone(),
+                None => return Err(syn::Error::new(tp.span(), "unsupported field type")),
+            };
 
             if outer_ty == *"Option" {
-                let args = tp.path.segments.last().unwrap().clone().arguments;
+                let args = &tp.path.segments.last().unwrap().arguments;
                 let inner_ty = match args {
-                    syn::PathArguments::AngleBracketed(args) => args.args.first().unwrap().clone(),
-                    _ => panic!("Option must have a generic type"),
+                    syn::PathArguments::AngleBracketed(args) => args.args.first().cloned(),
+                    _ => None,
+                };
+                let Some(inner_ty) = inner_ty else {
+                    return Err(syn::Error::new(
+                        tp.span(),
+                        "Option must have a generic type",
+                    ));
                 };
 
-                (ident, outer_ty, Some(inner_ty))
+                Ok((ident, outer_ty, Some(inner_ty)))
             } else {
-                (ident, outer_ty, None)
+                Ok((ident, outer_ty, None))
             }
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
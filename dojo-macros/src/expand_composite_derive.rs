@@ -0,0 +1,101 @@
+use quote::format_ident;
+use syn::{DeriveInput, Fields};
+
+use crate::common::{named_fields, reject_generics};
+
+#[derive(deluxe::ExtractAttributes)]
+#[deluxe(attributes(dojo))]
+struct CompositeStructAttrs {
+    name: String,
+}
+
+pub fn expand_composite_derive(mut ast: DeriveInput) -> deluxe::Result<proc_macro2::TokenStream> {
+    let fields = Fields::Named(named_fields(&ast, "Type")?);
+    reject_generics(&ast, "Type")?;
+
+    // Extract the attributes from the input
+    let CompositeStructAttrs { name } = deluxe::extract_attributes(&mut ast)?;
+
+    let ident = &ast.ident;
+    let field_idents = fields
+        .iter()
+        .filter_map(|f| f.ident.clone())
+        .collect::<Vec<_>>();
+    let field_names = field_idents
+        .iter()
+        .map(|ident| ident.to_string().trim_start_matches("r#").to_string())
+        .collect::<Vec<_>>();
+    let field_tys = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    // prefixed so fields can't shadow the locals of `from_sql`
+    let field_vars = field_names
+        .iter()
+        .map(|name| format_ident!("__dojo_{}", name))
+        .collect::<Vec<_>>();
+
+    // Define the output tokens
+    let expanded = quote::quote! {
+        impl dojo_orm::types::ToSql for #ident {
+            fn to_sql(
+                &self,
+                ty: &dojo_orm::types::Type,
+                out: &mut dojo_orm::bytes::BytesMut,
+            ) -> std::result::Result<dojo_orm::types::IsNull, std::boxed::Box<dyn std::error::Error + Sync + Send>>
+            where
+                Self: Sized,
+            {
+                let fields = dojo_orm::types::composite::fields(ty)?;
+                dojo_orm::types::composite::write_header(fields.len(), out);
+                for field in fields {
+                    match field.name() {
+                        #(#field_names => dojo_orm::types::composite::write_field(&self.#field_idents, field, out)?,)*
+                        name => return std::result::Result::Err(std::format!("unknown field {} in {}", name, ty.name()).into()),
+                    }
+                }
+
+                std::result::Result::Ok(dojo_orm::types::IsNull::No)
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool
+            where
+                Self: Sized,
+            {
+                dojo_orm::types::composite::accepts(ty, #name, &[
+                    #((#field_names, <#field_tys as dojo_orm::types::ToSql>::accepts as fn(&dojo_orm::types::Type) -> bool)),*
+                ])
+            }
+
+            dojo_orm::types::to_sql_checked!();
+        }
+
+        impl<'a> dojo_orm::types::FromSql<'a> for #ident {
+            fn from_sql(
+                ty: &dojo_orm::types::Type,
+                mut raw: &'a [u8],
+            ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error + Sync + Send>> {
+                let fields = dojo_orm::types::composite::fields(ty)?;
+                dojo_orm::types::composite::read_header(&mut raw, fields)?;
+
+                #(let mut #field_vars: std::option::Option<#field_tys> = std::option::Option::None;)*
+                for field in fields {
+                    match field.name() {
+                        #(#field_names => #field_vars = std::option::Option::Some(dojo_orm::types::composite::read_field(&mut raw, field)?),)*
+                        name => return std::result::Result::Err(std::format!("unknown field {} in {}", name, ty.name()).into()),
+                    }
+                }
+                dojo_orm::types::composite::read_end(raw)?;
+
+                std::result::Result::Ok(#ident {
+                    #(#field_idents: #field_vars.ok_or(std::concat!("missing field ", #field_names))?,)*
+                })
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool {
+                dojo_orm::types::composite::accepts(ty, #name, &[
+                    #((#field_names, <#field_tys as dojo_orm::types::FromSql<'a>>::accepts as fn(&dojo_orm::types::Type) -> bool)),*
+                ])
+            }
+        }
+    };
+
+    Ok(expanded)
+}
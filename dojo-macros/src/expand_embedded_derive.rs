@@ -1,8 +1,11 @@
+use crate::common::reject_generics;
+
 pub fn expand_embedded_derive(
     input: proc_macro2::TokenStream,
 ) -> deluxe::Result<proc_macro2::TokenStream> {
     // Parse the input tokens into a syntax tree
     let ast = syn::parse2::<syn::DeriveInput>(input)?;
+    reject_generics(&ast, "EmbeddedModel")?;
 
     // Define impl variables
     let ident = &ast.ident;
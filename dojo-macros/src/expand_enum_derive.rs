@@ -1,11 +1,74 @@
 use convert_case::{Case, Casing};
-use syn::Data;
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::{Data, Expr, ExprLit, Fields, Lit};
+
+use crate::common::reject_generics;
+use crate::expand_composite_derive::expand_composite_derive;
+
+const RENAME_ALL_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "kebab-case",
+    "UPPER_SNAKE_CASE",
+];
+
+const STORAGES: &[&str] = &["enum", "text", "int"];
+
+const REPRS: &[&str] = &["i16", "i32", "i64"];
 
 #[derive(deluxe::ExtractAttributes)]
 #[deluxe(attributes(dojo))]
 struct EnumStructAttrs {
-    name: String,
-    rename_all: String,
+    #[deluxe(default)]
+    name: Option<String>,
+    #[deluxe(default)]
+    rename_all: Option<syn::LitStr>,
+    #[deluxe(default)]
+    storage: Option<syn::LitStr>,
+    #[deluxe(default)]
+    repr: Option<syn::Ident>,
+}
+
+#[derive(deluxe::ExtractAttributes)]
+#[deluxe(attributes(dojo))]
+struct EnumVariantAttrs {
+    #[deluxe(default)]
+    rename: Option<String>,
+    #[deluxe(default)]
+    value: Option<syn::LitInt>,
+}
+
+fn apply_rename_all(s: String, rename_all: &str) -> String {
+    match rename_all {
+        "lowercase" => s.to_case(Case::Lower),
+        "UPPERCASE" => s.to_case(Case::Upper),
+        "PascalCase" => s.to_case(Case::Pascal),
+        "camelCase" => s.to_case(Case::Camel),
+        "snake_case" => s.to_case(Case::Snake),
+        "kebab-case" => s.to_case(Case::Kebab),
+        "UPPER_SNAKE_CASE" => s.to_case(Case::ScreamingSnake),
+        _ => s,
+    }
+}
+
+fn check_one_of(value: &syn::LitStr, allowed: &[&str], attr: &str) -> deluxe::Result<String> {
+    if allowed.contains(&value.value().as_str()) {
+        return Ok(value.value());
+    }
+
+    Err(syn::Error::new(
+        value.span(),
+        format!(
+            "unknown {} `{}`, expected one of: {}",
+            attr,
+            value.value(),
+            allowed.join(", ")
+        ),
+    ))
 }
 
 pub fn expand_enum_derive(
@@ -14,55 +77,174 @@ pub fn expand_enum_derive(
     // Parse the input tokens into a syntax tree
     let mut ast = syn::parse2::<syn::DeriveInput>(input)?;
 
+    if matches!(ast.data, Data::Struct(_)) {
+        return expand_composite_derive(ast);
+    }
+    reject_generics(&ast, "Type")?;
+
     // Extract the attributes from the input
-    let EnumStructAttrs { name, rename_all } = deluxe::extract_attributes(&mut ast)?;
+    let EnumStructAttrs {
+        name,
+        rename_all,
+        storage,
+        repr,
+    } = deluxe::extract_attributes(&mut ast)?;
+
+    let variants = match &mut ast.data {
+        Data::Enum(data) => &mut data.variants,
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "Type can only be derived for enums and structs",
+            ))
+        }
+        Data::Struct(_) => unreachable!(),
+    };
+
+    let rename_all = rename_all
+        .map(|rule| check_one_of(&rule, RENAME_ALL_RULES, "rename_all rule"))
+        .transpose()?;
+    let storage = storage
+        .map(|storage| check_one_of(&storage, STORAGES, "storage"))
+        .transpose()?
+        .unwrap_or_else(|| "enum".to_string());
+
+    let errors = deluxe::Errors::new();
+    let mut idents = vec![];
+    let mut labels: Vec<String> = vec![];
+    let mut values: Vec<(i64, Span)> = vec![];
+    for variant in variants.iter_mut() {
+        if !matches!(variant.fields, Fields::Unit) {
+            errors.push(
+                variant.fields.span(),
+                "Type can only be derived for enums with unit variants",
+            );
+            continue;
+        }
+
+        let EnumVariantAttrs { rename, value } = match deluxe::extract_attributes(variant) {
+            Ok(attrs) => attrs,
+            Err(e) => {
+                errors.push_syn(e);
+                continue;
+            }
+        };
+
+        let label = match rename {
+            Some(label) => label,
+            None => apply_rename_all(
+                variant.ident.to_string(),
+                rename_all.as_deref().unwrap_or(""),
+            ),
+        };
+        if labels.contains(&label) {
+            errors.push(variant.ident.span(), format!("duplicate label `{}`", label));
+        }
+
+        // an explicit `#[dojo(value = ..)]` wins over the discriminant
+        let discriminant = match &variant.discriminant {
+            Some((
+                _,
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(lit), ..
+                }),
+            )) => Some(lit.clone()),
+            _ => None,
+        };
+        if let Some(value) = value.or(discriminant) {
+            match value.base10_parse::<i64>() {
+                Ok(v) => values.push((v, value.span())),
+                Err(e) => errors.push_syn(e),
+            }
+        } else if storage == "int" {
+            errors.push(
+                variant.ident.span(),
+                "int storage needs a `#[dojo(value = ..)]` on every variant",
+            );
+        }
+
+        idents.push(variant.ident.clone());
+        labels.push(label);
+    }
+    errors.check()?;
 
     let ident = &ast.ident;
-    let variants = match ast.data.clone() {
-        Data::Enum(data) => data.variants,
-        _ => panic!("Table can only be derived for structs"),
+
+    let expanded = match storage.as_str() {
+        "text" => expand_text(ident, &idents, &labels),
+        "int" => {
+            let repr = repr.unwrap_or_else(|| syn::Ident::new("i32", Span::call_site()));
+            if !REPRS.contains(&repr.to_string().as_str()) {
+                return Err(syn::Error::new(
+                    repr.span(),
+                    format!(
+                        "unknown repr `{}`, expected one of: {}",
+                        repr,
+                        REPRS.join(", ")
+                    ),
+                ));
+            }
+
+            let errors = deluxe::Errors::new();
+            for (i, (value, span)) in values.iter().enumerate() {
+                let in_range = match repr.to_string().as_str() {
+                    "i16" => i16::try_from(*value).is_ok(),
+                    "i32" => i32::try_from(*value).is_ok(),
+                    _ => true,
+                };
+                if !in_range {
+                    errors.push(*span, format!("value {} doesn't fit in {}", value, repr));
+                }
+                if values[..i].iter().any(|(other, _)| other == value) {
+                    errors.push(*span, format!("duplicate value {}", value));
+                }
+            }
+            errors.check()?;
+
+            let values = values
+                .iter()
+                .map(|(value, _)| {
+                    syn::LitInt::new(&format!("{}{}", value, repr), Span::call_site())
+                })
+                .collect::<Vec<_>>();
+            expand_int(ident, &idents, &values, &repr)
+        }
+        _ => {
+            let Some(name) = name else {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "enum storage needs the Postgres type: `#[dojo(name = \"..\")]`",
+                ));
+            };
+            expand_enum(ident, &idents, &labels, &name)
+        }
     };
 
-    // Get the field idents
-    let field_idents = variants
-        .clone()
-        .into_iter()
-        .map(|f| f.ident)
-        .collect::<Vec<_>>();
-
-    let field_idents_str = field_idents
-        .iter()
-        .map(|i| {
-            let s = i.to_string();
-            match rename_all.as_str() {
-                "lowercase" => s.to_case(Case::Lower),
-                "UPPERCASE" => s.to_case(Case::Upper),
-                "PascalCase" => s.to_case(Case::Pascal),
-                "camelCase" => s.to_case(Case::Camel),
-                "snake_case" => s.to_case(Case::Snake),
-                "kebab-case" => s.to_case(Case::Kebab),
-                "UPPER_SNAKE_CASE" => s.to_case(Case::ScreamingSnake),
-                _ => s,
-            }
-        })
-        .collect::<Vec<_>>();
-
-    // Define the output tokens
-    let expanded = quote::quote! {
+    // Return the generated impl
+    Ok(expanded)
+}
+
+fn expand_enum(
+    ident: &syn::Ident,
+    idents: &[syn::Ident],
+    labels: &[String],
+    name: &str,
+) -> proc_macro2::TokenStream {
+    quote::quote! {
         impl dojo_orm::types::ToSql for #ident {
             fn to_sql(
                 &self,
                 ty: &dojo_orm::types::Type,
                 out: &mut dojo_orm::bytes::BytesMut,
-            ) -> Result<dojo_orm::types::IsNull, Box<dyn std::error::Error + Sync + Send>>
+            ) -> std::result::Result<dojo_orm::types::IsNull, std::boxed::Box<dyn std::error::Error + Sync + Send>>
             where
                 Self: Sized,
             {
                 let s = match *self {
-                    #(#ident::#field_idents => #field_idents_str),*
+                    #(#ident::#idents => #labels),*
                 };
                 out.extend_from_slice(s.as_bytes());
-                Ok(dojo_orm::types::IsNull::No)
+                std::result::Result::Ok(dojo_orm::types::IsNull::No)
             }
 
             fn accepts(ty: &dojo_orm::types::Type) -> bool
@@ -75,7 +257,7 @@ pub fn expand_enum_derive(
 
                 match *ty.kind() {
                     dojo_orm::types::Kind::Enum(ref variants) => {
-                        variants.iter().any(|v| #(v == #field_idents_str)||*)
+                        variants.iter().any(|v| #(v == #labels)||*)
                     }
                     _ => false,
                 }
@@ -88,10 +270,10 @@ pub fn expand_enum_derive(
             fn from_sql(
                 _ty: &dojo_orm::types::Type,
                 raw: &[u8],
-            ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+            ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error + Sync + Send>> {
                 match std::str::from_utf8(raw)? {
-                    #(#field_idents_str => Ok(#ident::#field_idents)),*,
-                    _ => Err("Unrecognized enum variant".into()),
+                    #(#labels => std::result::Result::Ok(#ident::#idents)),*,
+                    _ => std::result::Result::Err("Unrecognized enum variant".into()),
                 }
             }
 
@@ -102,14 +284,141 @@ pub fn expand_enum_derive(
 
                 match *ty.kind() {
                     dojo_orm::types::Kind::Enum(ref variants) => {
-                        variants.iter().any(|v| #(v == #field_idents_str)||* )
+                        variants.iter().any(|v| #(v == #labels)||* )
                     }
                     _ => false,
                 }
             }
         }
-    };
 
-    // Return the generated impl
-    Ok(expanded)
+        impl From<&#ident> for dojo_orm::Value {
+            fn from(value: &#ident) -> Self {
+                let s = match *value {
+                    #(#ident::#idents => #labels),*
+                };
+                dojo_orm::Value::Enum(s.to_string())
+            }
+        }
+    }
+}
+
+fn expand_text(
+    ident: &syn::Ident,
+    idents: &[syn::Ident],
+    labels: &[String],
+) -> proc_macro2::TokenStream {
+    quote::quote! {
+        impl #ident {
+            fn __dojo_label(&self) -> &'static str {
+                match *self {
+                    #(#ident::#idents => #labels),*
+                }
+            }
+        }
+
+        impl dojo_orm::types::ToSql for #ident {
+            fn to_sql(
+                &self,
+                ty: &dojo_orm::types::Type,
+                out: &mut dojo_orm::bytes::BytesMut,
+            ) -> std::result::Result<dojo_orm::types::IsNull, std::boxed::Box<dyn std::error::Error + Sync + Send>>
+            where
+                Self: Sized,
+            {
+                dojo_orm::types::ToSql::to_sql(&self.__dojo_label(), ty, out)
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool
+            where
+                Self: Sized,
+            {
+                <&str as dojo_orm::types::ToSql>::accepts(ty)
+            }
+
+            dojo_orm::types::to_sql_checked!();
+        }
+
+        impl<'a> dojo_orm::types::FromSql<'a> for #ident {
+            fn from_sql(
+                ty: &dojo_orm::types::Type,
+                raw: &'a [u8],
+            ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error + Sync + Send>> {
+                match <&str as dojo_orm::types::FromSql>::from_sql(ty, raw)? {
+                    #(#labels => std::result::Result::Ok(#ident::#idents)),*,
+                    _ => std::result::Result::Err("Unrecognized enum variant".into()),
+                }
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool {
+                <&str as dojo_orm::types::FromSql>::accepts(ty)
+            }
+        }
+
+        impl From<&#ident> for dojo_orm::Value {
+            fn from(value: &#ident) -> Self {
+                dojo_orm::Value::String(value.__dojo_label().to_string())
+            }
+        }
+    }
+}
+
+fn expand_int(
+    ident: &syn::Ident,
+    idents: &[syn::Ident],
+    values: &[syn::LitInt],
+    repr: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    quote::quote! {
+        impl #ident {
+            fn __dojo_value(&self) -> #repr {
+                match *self {
+                    #(#ident::#idents => #values),*
+                }
+            }
+        }
+
+        impl dojo_orm::types::ToSql for #ident {
+            fn to_sql(
+                &self,
+                ty: &dojo_orm::types::Type,
+                out: &mut dojo_orm::bytes::BytesMut,
+            ) -> std::result::Result<dojo_orm::types::IsNull, std::boxed::Box<dyn std::error::Error + Sync + Send>>
+            where
+                Self: Sized,
+            {
+                dojo_orm::types::ToSql::to_sql(&self.__dojo_value(), ty, out)
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool
+            where
+                Self: Sized,
+            {
+                <#repr as dojo_orm::types::ToSql>::accepts(ty)
+            }
+
+            dojo_orm::types::to_sql_checked!();
+        }
+
+        impl<'a> dojo_orm::types::FromSql<'a> for #ident {
+            fn from_sql(
+                ty: &dojo_orm::types::Type,
+                raw: &'a [u8],
+            ) -> std::result::Result<Self, std::boxed::Box<dyn std::error::Error + Sync + Send>> {
+                match <#repr as dojo_orm::types::FromSql>::from_sql(ty, raw)? {
+                    #(#values => std::result::Result::Ok(#ident::#idents)),*,
+                    value => std::result::Result::Err(std::format!("Unrecognized enum value {}", value).into()),
+                }
+            }
+
+            fn accepts(ty: &dojo_orm::types::Type) -> bool {
+                <#repr as dojo_orm::types::FromSql>::accepts(ty)
+            }
+        }
+
+        impl From<&#ident> for dojo_orm::Value {
+            fn from(value: &#ident) -> Self {
+                <dojo_orm::Value as std::convert::From<#repr>>::from(value.__dojo_value())
+            }
+        }
+    }
 }
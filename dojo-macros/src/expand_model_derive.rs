@@ -1,15 +1,17 @@
-use quote::quote;
+use quote::{format_ident, quote};
 use std::collections::HashMap;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
 
-const SUPPORTED_TYPES: &[&str] = &["i32", "i64", "Uuid", "String", "NaiveDateTime"];
+use crate::common::{named_fields, reject_generics};
 
 #[derive(deluxe::ExtractAttributes)]
 #[deluxe(attributes(dojo))]
 struct ModelStructAttrs {
     name: String,
     #[deluxe(default)]
-    sort_keys: Vec<String>,
+    sort_keys: Vec<syn::LitStr>,
+    #[deluxe(default)]
+    emit_changes: bool,
 }
 
 #[derive(deluxe::ExtractAttributes)]
@@ -19,6 +21,25 @@ struct ModelFieldAttributes {
     skip: bool,
 }
 
+fn option_inner_ty(ty: &Type) -> Option<&Type> {
+    let Type::Path(tp) = ty else {
+        return None;
+    };
+
+    let segment = tp.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn extract_model_field_attributes(
     ast: &mut DeriveInput,
 ) -> deluxe::Result<HashMap<String, crate::expand_model_derive::ModelFieldAttributes>> {
@@ -26,9 +47,11 @@ fn extract_model_field_attributes(
 
     if let Data::Struct(s) = &mut ast.data {
         for field in s.fields.iter_mut() {
-            let field_name = field.ident.as_ref().unwrap().to_string();
-            let attrs = deluxe::extract_attributes(field)?;
-            field_attrs.insert(field_name, attrs);
+            if let Some(ident) = &field.ident {
+                let field_name = ident.to_string();
+                let attrs = deluxe::extract_attributes(field)?;
+                field_attrs.insert(field_name, attrs);
+            }
         }
     }
 
@@ -41,19 +64,21 @@ pub fn expand_model_derive(
     // Parse the input tokens into a syntax tree
     let mut ast = syn::parse2::<syn::DeriveInput>(input)?;
 
+    let fields = Fields::Named(named_fields(&ast, "Model")?);
+    reject_generics(&ast, "Model")?;
+
     // Extract the attributes from the input
-    let ModelStructAttrs { name, sort_keys } = deluxe::extract_attributes(&mut ast)?;
+    let ModelStructAttrs {
+        name,
+        sort_keys,
+        emit_changes,
+    } = deluxe::extract_attributes(&mut ast)?;
     let field_attrs = extract_model_field_attributes(&mut ast)?;
 
     // Define impl variables
     let ident = &ast.ident;
     let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    let fields: Fields = match ast.data.clone() {
-        Data::Struct(data) => data.fields,
-        _ => panic!("Table can only be derived for structs"),
-    };
-
     // Get the field idents
     let field_idents = fields
         .clone()
@@ -63,33 +88,59 @@ pub fn expand_model_derive(
 
     let ident_columns = field_idents
         .iter()
-        .filter_map(|ident| {
+        .filter(|ident| {
             let skip = field_attrs
                 .get(&ident.to_string())
                 .map(|attrs| attrs.skip)
                 .unwrap_or(false);
 
-            if !skip {
-                Some(ident)
-            } else {
-                None
-            }
+            !skip
         })
         .collect::<Vec<_>>();
 
+    let errors = deluxe::Errors::new();
+    for key in &sort_keys {
+        if !ident_columns.iter().any(|ident| *ident == &key.value()) {
+            errors.push(
+                key.span(),
+                format!("sort key `{}` is not a column of `{}`", key.value(), ident),
+            );
+        }
+    }
+    errors.check()?;
+    let sort_keys = sort_keys.iter().map(|key| key.value()).collect::<Vec<_>>();
+
+    let nullable_columns = fields
+        .iter()
+        .filter(|f| option_inner_ty(&f.ty).is_some())
+        .filter_map(|f| f.ident.as_ref())
+        .filter(|ident| ident_columns.contains(ident))
+        .map(|ident| ident.to_string())
+        .collect::<Vec<_>>();
+
+    // fields without a conversion into `Value` can't be used in cursors
     let supported_values = fields
         .iter()
         .map(|f| {
-            let ty = &f.ty;
             let ident = f.ident.clone().unwrap();
-
-            if SUPPORTED_TYPES.contains(&quote::quote!(#ty).to_string().as_str()) {
-                quote::quote! {
-                    stringify!(#ident) => Some(dojo_orm::Value::from(&self.#ident)),
+            quote::quote! {
+                stringify!(#ident) => {
+                    use dojo_orm::{NoValue as _, ToValue as _};
+                    (&dojo_orm::ValueProbe(&self.#ident)).to_value()
                 }
-            } else {
-                quote::quote! {
-                    stringify!(#ident) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let has_values = fields
+        .iter()
+        .map(|f| {
+            let ident = f.ident.clone().unwrap();
+            let ty = &f.ty;
+            quote::quote! {
+                stringify!(#ident) => {
+                    use dojo_orm::{HasNoValue as _, HasValue as _};
+                    (&dojo_orm::TypeProbe::<#ty>(std::marker::PhantomData)).has_value()
                 }
             }
         })
@@ -115,13 +166,59 @@ pub fn expand_model_derive(
         })
         .collect::<Vec<_>>();
 
+    // COPY rows have no column names, non skipped fields are read in order
+    let mut copy_index = 0usize;
+    let copy_fields = field_idents
+        .iter()
+        .map(|ident| {
+            if ident_columns.contains(&ident) {
+                let index = copy_index;
+                copy_index += 1;
+                quote! {
+                    #ident: row.try_get(#index)?,
+                }
+            } else {
+                quote! {
+                    #ident: Default::default(),
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
     let columns = ident_columns
         .iter()
         .map(|ident| ident.to_string())
         .collect::<Vec<_>>();
 
+    // typed column references, e.g. `User::EMAIL_COL`
+    let column_consts = fields
+        .iter()
+        .filter(|f| f.ident.as_ref().is_some_and(|ident| ident_columns.contains(&ident)))
+        .map(|f| {
+            let ident = f.ident.as_ref().unwrap();
+            let ty = &f.ty;
+            let column = ident.to_string();
+            let column = column.trim_start_matches("r#");
+            let const_ident = format_ident!("{}_COL", column.to_uppercase());
+
+            quote! {
+                pub const #const_ident: dojo_orm::Column<Self, #ty> = dojo_orm::Column::new(#column);
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let emit_changes = emit_changes.then(|| {
+        quote! {
+            impl #impl_generics dojo_orm::EmitChanges for #ident #ty_generics #where_clause {}
+        }
+    });
+
     // Define the output tokens
     let expanded = quote::quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#column_consts)*
+        }
+
         #[async_trait::async_trait]
         impl #impl_generics dojo_orm::Model for #ident #ty_generics #where_clause {
             const NAME: &'static str = #name;
@@ -130,6 +227,10 @@ pub fn expand_model_derive(
                 #(#columns),*
             ];
 
+            const NULLABLE_COLUMNS: &'static [&'static str] = &[
+                #(#nullable_columns),*
+            ];
+
             fn params(&self) -> Vec<&(dyn dojo_orm::types::ToSql + Sync)> {
                 vec![#(&self.#ident_columns),*]
             }
@@ -140,6 +241,12 @@ pub fn expand_model_derive(
                 })
             }
 
+            fn from_copy_row(row: &tokio_postgres::binary_copy::BinaryCopyOutRow) -> anyhow::Result<Self> {
+                Ok(#ident {
+                    #(#copy_fields)*
+                })
+            }
+
             fn get_value(&self, column: &str) -> Option<dojo_orm::Value> {
                 match column {
                     #(#supported_values)*
@@ -147,10 +254,19 @@ pub fn expand_model_derive(
                 }
             }
 
+            fn has_value(column: &str) -> bool {
+                match column {
+                    #(#has_values)*
+                    _ => false,
+                }
+            }
+
             fn sort_keys() -> Vec<String> {
                 vec![#(#sort_keys.to_string()),*]
             }
         }
+
+        #emit_changes
     };
 
     // Return the generated impl
@@ -1,4 +1,4 @@
-use crate::common::derive_get_fields_with_tys;
+use crate::common::{derive_get_fields_with_tys, named_fields, reject_generics};
 use std::collections::HashMap;
 use syn::{Data, DeriveInput, Fields};
 
@@ -16,9 +16,11 @@ fn extract_update_model_field_attributes(
 
     if let Data::Struct(s) = &mut ast.data {
         for field in s.fields.iter_mut() {
-            let field_name = field.ident.as_ref().unwrap().to_string();
-            let attrs = deluxe::extract_attributes(field)?;
-            field_attrs.insert(field_name, attrs);
+            if let Some(ident) = &field.ident {
+                let field_name = ident.to_string();
+                let attrs = deluxe::extract_attributes(field)?;
+                field_attrs.insert(field_name, attrs);
+            }
         }
     }
 
@@ -30,6 +32,8 @@ pub fn expand_update_model_derive(
 ) -> deluxe::Result<proc_macro2::TokenStream> {
     // Parse the input tokens into a syntax tree
     let mut ast = syn::parse2::<DeriveInput>(input)?;
+    let fields = Fields::Named(named_fields(&ast, "UpdateModel")?);
+    reject_generics(&ast, "UpdateModel")?;
 
     let field_attrs = extract_update_model_field_attributes(&mut ast)?;
 
@@ -37,11 +41,6 @@ pub fn expand_update_model_derive(
     let ident = &ast.ident;
     let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    let fields: Fields = match ast.data.clone() {
-        Data::Struct(data) => data.fields,
-        _ => panic!("Table can only be derived for structs"),
-    };
-
     // Get the field idents
     let field_idents = fields
         .clone()
@@ -54,7 +53,7 @@ pub fn expand_update_model_derive(
         .map(|i| i.to_string())
         .collect::<Vec<_>>();
 
-    let columns = derive_get_fields_with_tys(fields.clone())
+    let columns = derive_get_fields_with_tys(fields.clone())?
         .into_iter()
         .map(|(ident, outer_ty, _inner_ty)| {
             let nullable = field_attrs
@@ -76,7 +75,7 @@ pub fn expand_update_model_derive(
         })
         .collect::<Vec<_>>();
 
-    let params = derive_get_fields_with_tys(fields)
+    let params = derive_get_fields_with_tys(fields)?
         .into_iter()
         .map(|(ident, outer_ty, _inner_ty)| {
             let nullable = field_attrs
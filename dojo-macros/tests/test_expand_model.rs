@@ -2,6 +2,7 @@ use dojo_macros::Model;
 
 #[test]
 fn test_expand_model() {
+    #[allow(dead_code)]
     #[derive(Model)]
     #[dojo(name = "users", sort_keys = ["name"])]
     struct User {
@@ -9,4 +10,6 @@ fn test_expand_model() {
         #[dojo(skip)]
         full_name: String,
     }
+
+    assert_eq!(User::NAME_COL.name(), "name");
 }
@@ -0,0 +1,9 @@
+use dojo_macros::Model;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users")]
+enum User {
+    Admin,
+}
+
+fn main() {}
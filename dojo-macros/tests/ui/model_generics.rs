@@ -0,0 +1,9 @@
+use dojo_macros::Model;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users")]
+struct User<T> {
+    id: T,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use dojo_macros::Model;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users")]
+struct User(String);
+
+fn main() {}
@@ -0,0 +1,20 @@
+use dojo_macros::Model;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users", sort_keys = ["created_at", "idd"])]
+struct User {
+    id: i32,
+    created_at: i64,
+    #[dojo(skip)]
+    full_name: String,
+}
+
+#[derive(Debug, Model)]
+#[dojo(name = "accounts", sort_keys = ["full_name"])]
+struct Account {
+    id: i32,
+    #[dojo(skip)]
+    full_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use dojo_macros::Type;
+
+#[derive(Debug, Type)]
+#[dojo(storage = "int", repr = i16)]
+enum Priority {
+    #[dojo(value = 1)]
+    Low,
+    High,
+}
+
+#[derive(Debug, Type)]
+#[dojo(storage = "int", repr = i16)]
+enum Level {
+    #[dojo(value = 1)]
+    Low,
+    #[dojo(value = 1)]
+    Medium,
+    #[dojo(value = 40000)]
+    High,
+}
+
+#[derive(Debug, Type)]
+#[dojo(storage = "json")]
+enum Kind {
+    Bug,
+}
+
+#[derive(Debug, Type)]
+enum Status {
+    Open,
+}
+
+fn main() {}
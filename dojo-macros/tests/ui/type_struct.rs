@@ -0,0 +1,14 @@
+use dojo_macros::Type;
+
+#[derive(Debug, Type)]
+#[dojo(name = "role")]
+struct Role(String);
+
+#[derive(Debug, Type)]
+#[dojo(name = "status", rename_all = "lowercase")]
+enum Status {
+    Active,
+    Banned(String),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use dojo_macros::Type;
+
+#[derive(Debug, Type)]
+#[dojo(name = "role", rename_all = "SCREAMING")]
+enum Role {
+    Admin,
+    User,
+}
+
+fn main() {}
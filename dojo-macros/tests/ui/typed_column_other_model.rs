@@ -0,0 +1,22 @@
+use dojo_macros::Model;
+use dojo_orm::predicates::equals;
+use dojo_orm::Database;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users", sort_keys = ["name"])]
+struct User {
+    name: String,
+}
+
+#[derive(Debug, Model)]
+#[dojo(name = "posts", sort_keys = ["title"])]
+struct Post {
+    title: String,
+}
+
+fn select(db: &Database) {
+    let mut posts = db.bind::<Post>();
+    posts.where_by(equals(User::NAME_COL, &"linh"));
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use dojo_macros::Model;
+use dojo_orm::predicates::equals;
+
+#[derive(Debug, Model)]
+#[dojo(name = "users", sort_keys = ["name"])]
+struct User {
+    name: String,
+}
+
+fn main() {
+    let _ = equals(User::NAME_COL, &1i32);
+}
@@ -0,0 +1,6 @@
+use dojo_macros::UpdateModel;
+
+#[derive(Debug, UpdateModel)]
+struct UpdateUser(Option<String>);
+
+fn main() {}
@@ -0,0 +1,195 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use strum::{Display, EnumString};
+
+use crate::model::Model;
+
+/// Implemented by `#[dojo(emit_changes)]` models, whose table publishes every
+/// inserted, updated and deleted row over NOTIFY once
+/// [`EmitChanges::changes_migration`] has been run.
+///
+/// Rows are sent as `row_to_json`, so the model's serde representation has to
+/// match the column names and their JSON encoding. Postgres rejects
+/// notifications of 8000 bytes or more, failing the statement, so larger
+/// changes only send the primary key, see [`Change::KeyOnly`].
+pub trait EmitChanges: Model {
+    /// The channel changes of the table are notified on.
+    fn changes_channel() -> String {
+        format!("dojo_changes_{}", Self::NAME.replace('.', "_"))
+    }
+
+    /// SQL installing the trigger that publishes the changes, safe to run
+    /// more than once.
+    fn changes_migration() -> String {
+        let name = Self::NAME.replace('.', "_");
+
+        format!(
+            r#"CREATE OR REPLACE FUNCTION dojo_{name}_changes() RETURNS trigger AS $$
+DECLARE
+    payload text;
+BEGIN
+    payload := json_build_object(
+        'op', TG_OP,
+        'old', CASE WHEN TG_OP IN ('UPDATE', 'DELETE') THEN row_to_json(OLD) END,
+        'new', CASE WHEN TG_OP IN ('INSERT', 'UPDATE') THEN row_to_json(NEW) END
+    )::text;
+    IF octet_length(payload) >= 8000 THEN
+        payload := json_build_object(
+            'op', TG_OP,
+            'key_only', true,
+            'key', (
+                SELECT json_object_agg(field.key, field.value)
+                FROM json_each(row_to_json(CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END)) field
+                JOIN pg_index i ON i.indrelid = TG_RELID AND i.indisprimary
+                JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY (i.indkey)
+                WHERE a.attname = field.key
+            )
+        )::text;
+    END IF;
+    PERFORM pg_notify('{channel}', payload);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS dojo_{name}_changes ON {table};
+CREATE TRIGGER dojo_{name}_changes
+    AFTER INSERT OR UPDATE OR DELETE ON {table}
+    FOR EACH ROW EXECUTE FUNCTION dojo_{name}_changes();
+"#,
+            name = name,
+            channel = Self::changes_channel(),
+            table = Self::NAME,
+        )
+    }
+}
+
+/// The statement that changed a row, as named by `TG_OP`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<T> {
+    Inserted(T),
+    Updated {
+        old: T,
+        new: T,
+    },
+    Deleted(T),
+    /// The change was too large to notify, only the primary key of the row
+    /// was sent, `null` for tables without one. The key is the new row's
+    /// unless deleted.
+    KeyOnly {
+        op: ChangeOp,
+        key: serde_json::Value,
+    },
+}
+
+#[derive(Deserialize)]
+struct RawChange {
+    op: String,
+    old: Option<serde_json::Value>,
+    new: Option<serde_json::Value>,
+    #[serde(default)]
+    key_only: bool,
+    key: Option<serde_json::Value>,
+}
+
+impl<T: DeserializeOwned> Change<T> {
+    pub(crate) fn from_payload(payload: &str) -> anyhow::Result<Self> {
+        let RawChange {
+            op,
+            old,
+            new,
+            key_only,
+            key,
+        } = serde_json::from_str(payload)?;
+        let op: ChangeOp = op
+            .parse()
+            .map_err(|_| anyhow::anyhow!("unknown change operation {}", op))?;
+        if key_only {
+            return Ok(Change::KeyOnly {
+                op,
+                key: key.unwrap_or_default(),
+            });
+        }
+
+        let row = |value: Option<serde_json::Value>| -> anyhow::Result<T> {
+            let value = value.ok_or_else(|| anyhow::anyhow!("{} change without its row", op))?;
+            Ok(serde_json::from_value(value)?)
+        };
+
+        match op {
+            ChangeOp::Insert => Ok(Change::Inserted(row(new)?)),
+            ChangeOp::Update => Ok(Change::Updated {
+                old: row(old)?,
+                new: row(new)?,
+            }),
+            ChangeOp::Delete => Ok(Change::Deleted(row(old)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn test_change_from_payload() -> anyhow::Result<()> {
+        let change = Change::<User>::from_payload(
+            r#"{"op": "UPDATE", "old": {"name": "linh1"}, "new": {"name": "linh2"}}"#,
+        )?;
+        assert_eq!(
+            change,
+            Change::Updated {
+                old: User {
+                    name: "linh1".to_string()
+                },
+                new: User {
+                    name: "linh2".to_string()
+                },
+            }
+        );
+
+        let change = Change::<User>::from_payload(
+            r#"{"op": "DELETE", "old": {"name": "linh1"}, "new": null}"#,
+        )?;
+        assert_eq!(
+            change,
+            Change::Deleted(User {
+                name: "linh1".to_string()
+            })
+        );
+
+        assert!(
+            Change::<User>::from_payload(r#"{"op": "INSERT", "old": null, "new": null}"#).is_err()
+        );
+
+        let change = Change::<User>::from_payload(
+            r#"{"op": "INSERT", "key_only": true, "key": {"id": 1}}"#,
+        )?;
+        assert_eq!(
+            change,
+            Change::KeyOnly {
+                op: ChangeOp::Insert,
+                key: serde_json::json!({"id": 1}),
+            }
+        );
+
+        assert!(Change::<User>::from_payload(
+            r#"{"op": "TRUNCATE", "key_only": true, "key": null}"#
+        )
+        .is_err());
+
+        Ok(())
+    }
+}
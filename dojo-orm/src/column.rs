@@ -0,0 +1,132 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use crate::predicates::{ModelPredicate, WherePredicate};
+
+/// A column of model `M` holding values of type `T`.
+///
+/// The `Model` derive generates one per field as an associated constant,
+/// e.g. `User::EMAIL_COL`, so predicates can check both the column name and
+/// the type of the value compared against it at compile time. Predicates on
+/// it are [`ModelPredicate`]s, which only operations on `M` accept.
+pub struct Column<M, T> {
+    name: &'static str,
+    _t: PhantomData<fn() -> (M, T)>,
+}
+
+impl<M, T> Column<M, T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _t: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<M, T> Clone for Column<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T> Copy for Column<M, T> {}
+
+impl<M, T> Debug for Column<M, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
+
+/// Anything predicates and orderings accept as a column: a plain name or a
+/// typed [`Column`].
+pub trait ColumnRef<'a> {
+    /// The predicates built on the column, a [`ModelPredicate`] for a typed
+    /// column so that only operations on its model accept them.
+    type Predicate: From<WherePredicate<'a>>;
+
+    fn column_name(&self) -> &'a str;
+}
+
+impl<'a> ColumnRef<'a> for &'a str {
+    type Predicate = WherePredicate<'a>;
+
+    fn column_name(&self) -> &'a str {
+        self
+    }
+}
+
+impl<'a> ColumnRef<'a> for &'a String {
+    type Predicate = WherePredicate<'a>;
+
+    fn column_name(&self) -> &'a str {
+        self.as_str()
+    }
+}
+
+impl<'a, M, T> ColumnRef<'a> for Column<M, T> {
+    type Predicate = ModelPredicate<'a, M>;
+
+    fn column_name(&self) -> &'a str {
+        self.name
+    }
+}
+
+/// Implemented by columns that can be compared against a `V`. Plain names
+/// accept anything, typed columns only values matching their field type.
+pub trait AcceptsValue<V: ?Sized> {}
+
+impl<V: ?Sized> AcceptsValue<V> for &str {}
+
+impl<V: ?Sized> AcceptsValue<V> for &String {}
+
+impl<M, T, V: ?Sized + ColumnValue<T>> AcceptsValue<V> for Column<M, T> {}
+
+/// Implemented by array columns whose elements can be compared against a `V`,
+/// for the predicates taking elements rather than whole arrays.
+pub trait AcceptsElement<V: ?Sized> {}
+
+impl<V: ?Sized> AcceptsElement<V> for &str {}
+
+impl<V: ?Sized> AcceptsElement<V> for &String {}
+
+impl<M, T, V: ?Sized + ColumnValue<T>> AcceptsElement<V> for Column<M, Vec<T>> {}
+
+impl<M, T, V: ?Sized + ColumnValue<T>> AcceptsElement<V> for Column<M, Option<Vec<T>>> {}
+
+/// Value types that can be compared with a column of type `T`.
+pub trait ColumnValue<T: ?Sized> {}
+
+impl<T: ?Sized> ColumnValue<T> for T {}
+
+impl<T: ?Sized> ColumnValue<T> for &T {}
+
+impl<T> ColumnValue<Option<T>> for T {}
+
+impl<T> ColumnValue<Option<T>> for &T {}
+
+impl ColumnValue<String> for str {}
+
+impl ColumnValue<String> for &str {}
+
+impl ColumnValue<Option<String>> for &str {}
+
+/// Lists whose items can be matched against a column with `in_list`.
+pub trait ListValue {
+    type Item;
+}
+
+impl<V> ListValue for Vec<V> {
+    type Item = V;
+}
+
+impl<V, const N: usize> ListValue for [V; N] {
+    type Item = V;
+}
+
+impl<V> ListValue for &[V] {
+    type Item = V;
+}
@@ -2,41 +2,106 @@ use std::fmt::Debug;
 use std::marker::PhantomData;
 
 use anyhow::Result;
-use tokio_postgres::Row;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio_postgres::{Config, Row};
 
+use crate::changes::{Change, EmitChanges};
+use crate::execution::Executor;
+use crate::listen::{listen, JsonNotification, Notification};
 use crate::model::{Model, UpdateModel};
+use crate::operations::copy;
 use crate::operations::*;
+use crate::pagination::{Cursor, CursorCodec};
 use crate::pool::*;
+use crate::raw::RawSql;
+use crate::transaction::Transaction;
+
+/// Upper bound on the number of rows a single select may return.
+///
+/// The guard applies to `all()`, awaiting a select directly and cursor page
+/// sizes. `stream()` is not guarded since it never buffers the result set.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MaxRows {
+    /// Fail the query when more rows than the limit match.
+    Error(i64),
+    /// Log a warning and return only the first rows up to the limit.
+    Warn(i64),
+}
+
+impl MaxRows {
+    pub fn rows(&self) -> i64 {
+        match self {
+            MaxRows::Error(rows) => *rows,
+            MaxRows::Warn(rows) => *rows,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Database {
     pool: Pool<PostgresConnectionManager<NoTls>>,
+    config: Config,
+    max_rows: Option<MaxRows>,
+    cursor_codec: CursorCodec,
 }
 
 impl Database {
     pub async fn new(url: &str) -> Result<Self> {
-        let manager = PostgresConnectionManager::new_from_stringlike(url, NoTls)?;
+        let config = url.parse::<Config>()?;
+        let manager = PostgresConnectionManager::new(config.clone(), NoTls);
         let pool = Pool::builder().build(manager).await?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            config,
+            max_rows: None,
+            cursor_codec: CursorCodec::default(),
+        })
+    }
+
+    /// Guards every select issued through this database, see [`MaxRows`].
+    pub fn with_max_rows(mut self, max_rows: MaxRows) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Signs and/or encrypts the cursors handed out by this database, through
+    /// [`Database::encode_cursor`] and the pages returned by `cursor()`.
+    pub fn with_cursor_codec(mut self, codec: CursorCodec) -> Self {
+        self.cursor_codec = codec;
+        self
+    }
+
+    pub fn encode_cursor(&self, cursor: &Cursor) -> Result<String> {
+        self.cursor_codec.encode(cursor)
+    }
+
+    pub fn decode_cursor(&self, encoded: &str) -> Result<Cursor> {
+        Ok(self.cursor_codec.decode(encoded)?)
     }
 
-    pub async fn get(&self) -> Result<PooledConnection<PostgresConnectionManager<NoTls>>> {
+    /// Starts a transaction on a dedicated connection. Operations created from
+    /// it run on that connection and it is rolled back unless committed.
+    pub async fn begin(&self) -> Result<Transaction> {
+        Transaction::begin(&self.pool, self.max_rows, self.cursor_codec.clone()).await
+    }
+
+    pub async fn get(&self) -> Result<PooledConnection<'_, PostgresConnectionManager<NoTls>>> {
         Ok(self.pool.get().await?)
     }
 
-    pub fn bind<T>(&self) -> SelectOperation<T>
+    pub fn bind<T>(&self) -> SelectOperation<'_, T>
     where
         T: Model + Debug,
     {
-        SelectOperation {
-            pool: &self.pool,
-            columns: T::COLUMNS,
-            params: vec![],
-            predicates: vec![],
-            order_by: vec![],
-            _t: PhantomData::<T>,
-        }
+        SelectOperation::new(
+            Executor::Pool(&self.pool),
+            self.max_rows,
+            &self.cursor_codec,
+        )
     }
 
     pub fn insert<'a, T>(&'a self, data: &'a [&'a T]) -> InsertOperation<'a, T>
@@ -44,39 +109,135 @@ impl Database {
         T: Model + Debug,
     {
         InsertOperation {
-            pool: &self.pool,
+            executor: Executor::Pool(&self.pool),
             data,
         }
     }
 
+    /// Inserts the rows `select` returns into `T`'s table, by default reading
+    /// the columns of `T` with the same names.
+    pub fn insert_from<'a, T>(
+        &'a self,
+        select: &'a SelectOperation<'a, impl Model + Debug>,
+    ) -> InsertFromOperation<'a, T>
+    where
+        T: Model + Debug,
+    {
+        InsertFromOperation {
+            executor: Executor::Pool(&self.pool),
+            source: select.source(),
+            columns: T::COLUMNS.to_vec(),
+            select_columns: T::COLUMNS.to_vec(),
+            _t: PhantomData,
+        }
+    }
+
     pub fn update<'a, T, U>(&'a self, data: &'a U) -> UpdateOperation<'a, T, U>
     where
         T: Model + Debug,
         U: UpdateModel,
     {
         UpdateOperation {
-            pool: &self.pool,
+            executor: Executor::Pool(&self.pool),
             columns: data.columns(),
             params: data.params(),
+            set_exprs: vec![],
             predicates: vec![],
             _t: PhantomData,
             _u: PhantomData,
         }
     }
 
-    pub fn delete<T>(&self) -> DeleteOperation<T>
+    pub fn delete<T>(&self) -> DeleteOperation<'_, T>
     where
         T: Model + Debug,
     {
         DeleteOperation {
-            pool: &self.pool,
+            executor: Executor::Pool(&self.pool),
             predicates: vec![],
             _t: PhantomData,
         }
     }
 
-    pub async fn raw_query(&self, query: &str) -> Result<Vec<Row>> {
+    /// Streams the notifications sent on `channels`, see [`Database::notify`].
+    ///
+    /// They are received on a dedicated connection that reconnects and LISTENs
+    /// again when it drops. Notifications sent while it is down are lost.
+    pub async fn listen(
+        &self,
+        channels: &[&str],
+    ) -> Result<BoxStream<'static, Result<Notification>>> {
+        listen(&self.config, channels).await
+    }
+
+    /// Like [`Database::listen`], deserializing every payload from JSON.
+    pub async fn listen_json<T>(
+        &self,
+        channels: &[&str],
+    ) -> Result<BoxStream<'static, Result<JsonNotification<T>>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let notifications = listen(&self.config, channels).await?;
+
+        Ok(notifications
+            .and_then(|notification| async move { Ok(JsonNotification::try_from(notification)?) })
+            .boxed())
+    }
+
+    /// Streams the rows inserted, updated and deleted in `T`'s table, see
+    /// [`EmitChanges`].
+    pub async fn changes<T>(&self) -> Result<BoxStream<'static, Result<Change<T>>>>
+    where
+        T: EmitChanges + DeserializeOwned + Send + 'static,
+    {
+        let channel = T::changes_channel();
+        let notifications = listen(&self.config, &[&channel]).await?;
+
+        Ok(notifications
+            .and_then(|notification| async move { Change::from_payload(notification.payload()) })
+            .boxed())
+    }
+
+    pub async fn notify(&self, channel: &str, payload: &str) -> Result<()> {
+        self.raw_execute(crate::sql!("SELECT pg_notify($1, $2)", &channel, &payload))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn notify_json<T: Serialize>(&self, channel: &str, payload: &T) -> Result<()> {
+        self.notify(channel, &serde_json::to_string(payload)?).await
+    }
+
+    /// Loads `rows` with a binary `COPY ... FROM STDIN`, returning the number
+    /// of rows written. Unlike `insert`, nothing is returned and there is no
+    /// limit on the number of rows.
+    pub async fn copy_in<'b, T, I>(&self, rows: I) -> Result<u64>
+    where
+        T: Model + 'b,
+        I: IntoIterator<Item = &'b T>,
+    {
+        copy::copy_in(Executor::Pool(&self.pool), rows).await
+    }
+
+    /// Streams every row of `T`'s table with a binary `COPY ... TO STDOUT`.
+    pub fn copy_out<'a, T>(&'a self) -> BoxStream<'a, Result<T>>
+    where
+        T: Model + Send + 'a,
+    {
+        copy::copy_out(Executor::Pool(&self.pool))
+    }
+
+    pub async fn raw_query<'a>(&self, sql: impl Into<RawSql<'a>>) -> Result<Vec<Row>> {
+        let conn = self.pool.get().await?;
+        let (stmt, params) = sql.into().build();
+        conn.query(&stmt, &params).await.map_err(Into::into)
+    }
+
+    pub async fn raw_execute<'a>(&self, sql: impl Into<RawSql<'a>>) -> Result<u64> {
         let conn = self.pool.get().await?;
-        conn.query(query, &[]).await.map_err(Into::into)
+        let (stmt, params) = sql.into().build();
+        conn.execute(&stmt, &params).await.map_err(Into::into)
     }
 }
@@ -1,28 +1,98 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::ops::Deref;
 
 use anyhow::Result;
-use bb8::Pool;
+use bb8::{Pool, PooledConnection};
 use bb8_postgres::PostgresConnectionManager;
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
 use postgres_types::FromSql;
-use tokio_postgres::{NoTls, Row};
+use tokio_postgres::{Client, NoTls, Row};
 use tracing::{debug, info};
+use uuid::Uuid;
 
+use crate::pagination::CursorCodec;
 use crate::query_builder::QueryBuilder;
+use crate::transaction::Transaction;
 use crate::Model;
 
+/// Where a statement runs: a fresh pooled connection per statement, or the
+/// connection holding an open transaction.
+#[derive(Clone, Copy)]
+pub(crate) enum Executor<'a> {
+    Pool(&'a Pool<PostgresConnectionManager<NoTls>>),
+    Client(&'a Client),
+}
+
+impl<'a> Executor<'a> {
+    pub(crate) async fn get(&self) -> Result<Connection<'a>> {
+        match self {
+            Executor::Pool(pool) => Ok(Connection::Pooled(pool.get().await?)),
+            Executor::Client(client) => Ok(Connection::Borrowed(client)),
+        }
+    }
+
+    pub(crate) fn in_transaction(&self) -> bool {
+        matches!(self, Executor::Client(_))
+    }
+
+    /// Runs the statements one after another and concatenates their rows. When
+    /// there are several, they share a transaction, opening one if needed.
+    pub(crate) async fn all_in_batches<T: Model + Debug>(
+        &self,
+        qbs: &[QueryBuilder<'_>],
+    ) -> Result<Vec<T>> {
+        // dropped on error, which rolls back the batches already inserted
+        let tx = match (self, qbs.len()) {
+            (Executor::Pool(pool), 2..) => {
+                Some(Transaction::begin(pool, None, CursorCodec::default()).await?)
+            }
+            _ => None,
+        };
+        let executor = tx.as_ref().map_or(*self, Transaction::executor);
+
+        let mut records = vec![];
+        for qb in qbs {
+            records.extend(Execution::new(executor, qb).all::<T>().await?);
+        }
+
+        if let Some(tx) = tx {
+            tx.commit().await?;
+        }
+
+        Ok(records)
+    }
+}
+
+pub(crate) enum Connection<'a> {
+    Pooled(PooledConnection<'a, PostgresConnectionManager<NoTls>>),
+    Borrowed(&'a Client),
+}
+
+impl Deref for Connection<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Connection::Pooled(conn) => conn,
+            Connection::Borrowed(client) => client,
+        }
+    }
+}
+
 pub struct Execution<'a> {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
+    pub(crate) executor: Executor<'a>,
     pub(crate) qb: &'a QueryBuilder<'a>,
 }
 
 impl<'a> Execution<'a> {
-    pub fn new(pool: &'a Pool<PostgresConnectionManager<NoTls>>, qb: &'a QueryBuilder<'a>) -> Self {
-        Self { pool, qb }
+    pub(crate) fn new(executor: Executor<'a>, qb: &'a QueryBuilder<'a>) -> Self {
+        Self { executor, qb }
     }
 
     pub async fn first_or_throw<T: Model + Debug>(&self) -> Result<T> {
-        let conn = self.pool.get().await?;
+        let conn = self.executor.get().await?;
         let (stmt, params) = self.qb.build_sql()?;
         info!(stmt);
 
@@ -34,7 +104,7 @@ impl<'a> Execution<'a> {
     }
 
     pub async fn query_one(&self) -> Result<Row> {
-        let conn = self.pool.get().await?;
+        let conn = self.executor.get().await?;
         let (stmt, params) = self.qb.build_sql()?;
         info!(stmt);
 
@@ -44,8 +114,23 @@ impl<'a> Execution<'a> {
         Ok(record)
     }
 
+    pub async fn estimate_rows(&self) -> Result<i64> {
+        let conn = self.executor.get().await?;
+        let (stmt, params) = self.qb.build_sql()?;
+        let stmt = format!("EXPLAIN (FORMAT JSON) {}", stmt);
+        info!(stmt);
+
+        let row = conn.query_one(&stmt, &params).await?;
+        let plan: serde_json::Value = row.get(0);
+        let rows = plan[0]["Plan"]["Plan Rows"]
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("query plan has no row estimate"))?;
+
+        Ok(rows as i64)
+    }
+
     pub async fn first<T: Model + Debug>(&self) -> Result<Option<T>> {
-        let conn = self.pool.get().await?;
+        let conn = self.executor.get().await?;
         let (stmt, params) = self.qb.build_sql()?;
         info!(stmt);
 
@@ -57,7 +142,7 @@ impl<'a> Execution<'a> {
     }
 
     pub async fn all<T: Model + Debug>(&self) -> Result<Vec<T>> {
-        let conn = self.pool.get().await?;
+        let conn = self.executor.get().await?;
         let (stmt, params) = self.qb.build_sql()?;
         info!(stmt);
 
@@ -70,4 +155,71 @@ impl<'a> Execution<'a> {
 
         Ok(records)
     }
+
+    pub async fn stream<T: Model + Debug>(
+        &self,
+        fetch_size: i32,
+        mut sender: Sender<Result<T>>,
+    ) -> Result<()> {
+        let (stmt, params) = self.qb.build_sql()?;
+        info!(stmt);
+
+        let pool = match self.executor {
+            Executor::Pool(pool) => pool,
+            Executor::Client(client) => {
+                // the client is already inside a transaction, where portals
+                // can't be bound, so the rows are fetched from a cursor
+                let cursor = format!("dojo_stream_{}", Uuid::new_v4().simple());
+                let declare = format!("DECLARE {} NO SCROLL CURSOR FOR {}", cursor, stmt);
+                client.execute(declare.as_str(), &params).await?;
+
+                let fetch = if fetch_size > 0 {
+                    format!("FETCH {} FROM {}", fetch_size, cursor)
+                } else {
+                    format!("FETCH ALL FROM {}", cursor)
+                };
+                'fetch: loop {
+                    let rows = client.query(fetch.as_str(), &[]).await?;
+                    let is_last = fetch_size <= 0 || rows.len() < fetch_size as usize;
+                    debug!(rows = rows.len(), "fetched rows from cursor");
+
+                    for row in rows {
+                        if sender.send(T::from_row(row)).await.is_err() {
+                            break 'fetch;
+                        }
+                    }
+
+                    if is_last {
+                        break;
+                    }
+                }
+                client.batch_execute(&format!("CLOSE {}", cursor)).await?;
+                return Ok(());
+            }
+        };
+
+        // portals only live inside a transaction
+        let mut conn = pool.get().await?;
+        let tx = conn.transaction().await?;
+        let portal = tx.bind(stmt.as_str(), &params).await?;
+        loop {
+            let rows = tx.query_portal(&portal, fetch_size).await?;
+            let is_last = fetch_size <= 0 || rows.len() < fetch_size as usize;
+            debug!(rows = rows.len(), "fetched rows from portal");
+
+            for row in rows {
+                if sender.send(T::from_row(row)).await.is_err() {
+                    // the receiver was dropped, nobody is listening anymore
+                    return Ok(());
+                }
+            }
+
+            if is_last {
+                break;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
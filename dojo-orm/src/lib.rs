@@ -1,23 +1,38 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+// lets the derives from dojo-macros be used inside this crate
+extern crate self as dojo_orm;
+
+pub use changes::{Change, ChangeOp, EmitChanges};
+pub use column::*;
 pub use database::*;
+pub use listen::{JsonNotification, Notification};
 pub use model::*;
+pub use transaction::*;
 
+mod changes;
+mod column;
 mod database;
 mod execution;
+mod listen;
 mod model;
 mod operations;
 pub mod order_by;
 pub mod pagination;
 pub mod predicates;
 mod query_builder;
+pub mod queue;
+pub mod raw;
+mod transaction;
 pub mod types;
 
 pub mod prelude {
     pub use crate::operations::*;
     pub use crate::order_by::*;
     pub use crate::predicates::*;
+    pub use crate::raw::*;
+    pub use crate::sql;
 }
 
 pub mod pool {
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{stream, StreamExt};
+use serde::de::DeserializeOwned;
+use tokio_postgres::{AsyncMessage, Client, Config, NoTls};
+use tracing::warn;
+
+pub use tokio_postgres::Notification;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A notification whose payload was deserialized from JSON.
+#[derive(Debug, Clone)]
+pub struct JsonNotification<T> {
+    pub process_id: i32,
+    pub channel: String,
+    pub payload: T,
+}
+
+impl<T: DeserializeOwned> TryFrom<Notification> for JsonNotification<T> {
+    type Error = serde_json::Error;
+
+    fn try_from(notification: Notification) -> Result<Self, Self::Error> {
+        Ok(Self {
+            process_id: notification.process_id(),
+            channel: notification.channel().to_string(),
+            payload: serde_json::from_str(notification.payload())?,
+        })
+    }
+}
+
+struct Listener {
+    config: Config,
+    channels: Vec<String>,
+    conn: Option<(Client, mpsc::UnboundedReceiver<Notification>)>,
+    delay: Duration,
+}
+
+/// Streams the notifications sent on `channels` from a connection outside of
+/// the pool.
+///
+/// When the connection drops, a new one is opened and LISTENs again; anything
+/// notified in between is lost. Failed reconnects are yielded as errors and
+/// retried with a growing delay.
+pub(crate) async fn listen(
+    config: &Config,
+    channels: &[&str],
+) -> anyhow::Result<BoxStream<'static, anyhow::Result<Notification>>> {
+    let channels = channels
+        .iter()
+        .map(|channel| channel.to_string())
+        .collect::<Vec<_>>();
+    let conn = connect(config, &channels).await?;
+
+    let listener = Listener {
+        config: config.clone(),
+        channels,
+        conn: Some(conn),
+        delay: MIN_RECONNECT_DELAY,
+    };
+
+    let notifications = stream::unfold(listener, |mut listener| async move {
+        loop {
+            if let Some((_, receiver)) = &mut listener.conn {
+                if let Some(notification) = receiver.next().await {
+                    return Some((Ok(notification), listener));
+                }
+
+                warn!(channels = ?listener.channels, "listen connection lost, reconnecting");
+                listener.conn = None;
+            }
+
+            match connect(&listener.config, &listener.channels).await {
+                Ok(conn) => {
+                    listener.conn = Some(conn);
+                    listener.delay = MIN_RECONNECT_DELAY;
+                }
+                Err(e) => {
+                    tokio::time::sleep(listener.delay).await;
+                    listener.delay = (listener.delay * 2).min(MAX_RECONNECT_DELAY);
+                    return Some((Err(e), listener));
+                }
+            }
+        }
+    });
+
+    Ok(notifications.boxed())
+}
+
+// Notifications are forwarded to the receiver until the connection fails or
+// the client is dropped, after which the receiver ends.
+async fn connect(
+    config: &Config,
+    channels: &[String],
+) -> anyhow::Result<(Client, mpsc::UnboundedReceiver<Notification>)> {
+    let (client, mut connection) = config.connect(NoTls).await?;
+    let (sender, receiver) = mpsc::unbounded();
+
+    tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(AsyncMessage::Notification(notification)) => {
+                    if sender.unbounded_send(notification).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(error = %e, "listen connection failed");
+                    break;
+                }
+            }
+        }
+    });
+
+    for channel in channels {
+        client
+            .batch_execute(&format!("LISTEN {}", quote_ident(channel)))
+            .await?;
+    }
+
+    Ok((client, receiver))
+}
+
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("jobs"), "\"jobs\"");
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+    }
+}
@@ -6,7 +6,9 @@ use chrono::NaiveDateTime;
 use postgres_types::{accepts, to_sql_checked};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::marker::PhantomData;
 use strum::{Display, EnumString};
+use tokio_postgres::binary_copy::BinaryCopyOutRow;
 use uuid::Uuid;
 
 macro_rules! impl_value {
@@ -22,12 +24,6 @@ macro_rules! impl_value {
                 Value::$variant(value.clone())
             }
         }
-
-        impl From<Option<$ty>> for Value {
-            fn from(_: Option<$ty>) -> Self {
-                Value::Null
-            }
-        }
     };
 }
 
@@ -39,49 +35,184 @@ pub enum Value {
     String(String),
     NaiveDateTime(NaiveDateTime),
     Null,
+    Int16(i16),
+    /// A label of a Postgres `ENUM`.
+    Enum(String),
 }
 
 impl_value!(Uuid, Uuid);
+impl_value!(i16, Int16);
 impl_value!(i32, Int32);
 impl_value!(i64, Int64);
 impl_value!(String, String);
 impl_value!(NaiveDateTime, NaiveDateTime);
 
+impl<T> From<Option<T>> for Value
+where
+    Value: From<T>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map(<Value as From<T>>::from).unwrap_or(Value::Null)
+    }
+}
+
+impl<'a, T> From<&'a Option<T>> for Value
+where
+    Value: From<&'a T>,
+{
+    fn from(value: &'a Option<T>) -> Self {
+        value
+            .as_ref()
+            .map(<Value as From<&'a T>>::from)
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Lets the `Model` derive turn any field into a [`Value`] when a conversion
+/// exists and fall back to `None` otherwise, without knowing the field type.
+#[doc(hidden)]
+pub struct ValueProbe<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ToValue {
+    fn to_value(&self) -> Option<Value>;
+}
+
+impl<'a, T> ToValue for ValueProbe<'a, T>
+where
+    Value: From<&'a T>,
+{
+    fn to_value(&self) -> Option<Value> {
+        Some(<Value as From<&'a T>>::from(self.0))
+    }
+}
+
+#[doc(hidden)]
+pub trait NoValue {
+    fn to_value(&self) -> Option<Value>;
+}
+
+impl<T> NoValue for &ValueProbe<'_, T> {
+    fn to_value(&self) -> Option<Value> {
+        None
+    }
+}
+
+/// [`ValueProbe`] without a value, tells whether a field type converts into
+/// a [`Value`] before any row is read.
+#[doc(hidden)]
+pub struct TypeProbe<T>(pub PhantomData<T>);
+
+#[doc(hidden)]
+pub trait HasValue {
+    fn has_value(&self) -> bool;
+}
+
+impl<T> HasValue for TypeProbe<T>
+where
+    Value: for<'a> From<&'a T>,
+{
+    fn has_value(&self) -> bool {
+        true
+    }
+}
+
+#[doc(hidden)]
+pub trait HasNoValue {
+    fn has_value(&self) -> bool;
+}
+
+impl<T> HasNoValue for &TypeProbe<T> {
+    fn has_value(&self) -> bool {
+        false
+    }
+}
+
 impl ToSql for Value {
     fn to_sql(
         &self,
         ty: &crate::types::Type,
         w: &mut bytes::BytesMut,
     ) -> std::result::Result<crate::types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        // `accepts` can't see the variant, so a value bound to a column of
+        // another width is refused here instead of being sent as is
         match self {
-            Value::Uuid(t) => t.to_sql(ty, w),
-            Value::Int32(t) => t.to_sql(ty, w),
-            Value::Int64(t) => t.to_sql(ty, w),
-            Value::String(t) => t.to_sql(ty, w),
-            Value::NaiveDateTime(t) => t.to_sql(ty, w),
+            Value::Uuid(t) => to_sql_exact(t, ty, w),
+            Value::Int32(t) => to_sql_exact(t, ty, w),
+            Value::Int64(t) => to_sql_exact(t, ty, w),
+            Value::String(t) => to_sql_exact(t, ty, w),
+            Value::NaiveDateTime(t) => to_sql_exact(t, ty, w),
             Value::Null => Ok(crate::types::IsNull::Yes),
+            Value::Int16(t) => to_sql_exact(t, ty, w),
+            Value::Enum(t) => {
+                w.extend_from_slice(t.as_bytes());
+                Ok(crate::types::IsNull::No)
+            }
         }
     }
 
-    accepts!(UUID, INT4, INT8, TEXT, TIMESTAMP);
+    fn accepts(ty: &crate::types::Type) -> bool {
+        use crate::types::{Kind, Type};
+
+        matches!(
+            *ty,
+            Type::UUID | Type::INT2 | Type::INT4 | Type::INT8 | Type::TEXT | Type::TIMESTAMP
+        ) || matches!(ty.kind(), Kind::Enum(_))
+    }
+
     to_sql_checked!();
 }
 
+fn to_sql_exact<T: ToSql>(
+    value: &T,
+    ty: &crate::types::Type,
+    w: &mut bytes::BytesMut,
+) -> std::result::Result<crate::types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+    if !T::accepts(ty) {
+        return Err(Box::new(crate::types::WrongType::new::<T>(ty.clone())));
+    }
+    value.to_sql(ty, w)
+}
+
 pub trait Model {
     const NAME: &'static str;
     const COLUMNS: &'static [&'static str];
+    const NULLABLE_COLUMNS: &'static [&'static str] = &[];
     fn params(&self) -> Vec<&(dyn ToSql + Sync)>;
     fn from_row(row: tokio_postgres::Row) -> Result<Self>
     where
         Self: Sized;
 
+    /// Reads a row of a binary `COPY ... TO STDOUT` of [`Model::COLUMNS`].
+    /// The derive implements it, other models can't be copied out unless
+    /// they do too.
+    fn from_copy_row(_row: &BinaryCopyOutRow) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(anyhow::anyhow!("{} can't be read from a COPY", Self::NAME))
+    }
+
     fn get_value(&self, column: &str) -> Option<Value>;
+
+    /// Whether [`Model::get_value`] returns the values of `column`, which
+    /// cursors can only be built from those columns.
+    fn has_value(column: &str) -> bool
+    where
+        Self: Sized,
+    {
+        Self::COLUMNS.contains(&column)
+    }
     fn sort_keys() -> Vec<String>;
     fn cursor(&self) -> Cursor {
+        self.cursor_by(&Self::sort_keys())
+    }
+
+    fn cursor_by(&self, keys: &[String]) -> Cursor {
         let mut values = vec![];
-        for key in Self::sort_keys() {
+        for key in keys {
             if let Some(value) = self.get_value(key.as_str()) {
-                values.push(Row::new(key, value));
+                values.push(Row::new(key.clone(), value));
             }
         }
 
@@ -94,3 +225,40 @@ pub trait UpdateModel {
     fn columns(&self) -> Vec<&'static str>;
     fn params(&self) -> Vec<&(dyn ToSql + Sync)>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Type;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_value_to_sql_checks_the_width() {
+        let mut buf = BytesMut::new();
+        assert!(Value::Int32(1)
+            .to_sql_checked(&Type::INT2, &mut buf)
+            .is_err());
+        assert!(Value::Int16(1)
+            .to_sql_checked(&Type::INT4, &mut buf)
+            .is_err());
+        assert!(Value::Int64(1)
+            .to_sql_checked(&Type::INT4, &mut buf)
+            .is_err());
+        assert!(Value::String("a".to_string())
+            .to_sql_checked(&Type::UUID, &mut buf)
+            .is_err());
+        assert!(buf.is_empty());
+
+        assert!(Value::Int16(1)
+            .to_sql_checked(&Type::INT2, &mut buf)
+            .is_ok());
+        assert!(Value::Int32(1)
+            .to_sql_checked(&Type::INT4, &mut buf)
+            .is_ok());
+        assert!(Value::Int64(1)
+            .to_sql_checked(&Type::INT8, &mut buf)
+            .is_ok());
+        assert!(Value::Null.to_sql_checked(&Type::INT2, &mut buf).is_ok());
+        assert_eq!(buf.len(), 2 + 4 + 8);
+    }
+}
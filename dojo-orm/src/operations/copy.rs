@@ -0,0 +1,78 @@
+use anyhow::Result;
+use futures::stream::BoxStream;
+use futures::{pin_mut, StreamExt, TryFutureExt};
+use tokio_postgres::binary_copy::{BinaryCopyInWriter, BinaryCopyOutStream};
+use tokio_postgres::Client;
+
+use crate::execution::Executor;
+use crate::model::Model;
+use crate::types::Type;
+
+// the server's types of `T::COLUMNS`, so enums and composites are encoded too
+async fn column_types<T: Model>(client: &Client) -> Result<Vec<Type>> {
+    let stmt = client
+        .prepare(&format!(
+            "SELECT {} FROM {} LIMIT 0",
+            T::COLUMNS.join(", "),
+            T::NAME
+        ))
+        .await?;
+
+    Ok(stmt
+        .columns()
+        .iter()
+        .map(|column| column.type_().clone())
+        .collect())
+}
+
+pub(crate) async fn copy_in<'b, T, I>(executor: Executor<'_>, rows: I) -> Result<u64>
+where
+    T: Model + 'b,
+    I: IntoIterator<Item = &'b T>,
+{
+    let conn = executor.get().await?;
+    let types = column_types::<T>(&conn).await?;
+
+    let sink = conn
+        .copy_in(&format!(
+            "COPY {} ({}) FROM STDIN (FORMAT binary)",
+            T::NAME,
+            T::COLUMNS.join(", ")
+        ))
+        .await?;
+    let writer = BinaryCopyInWriter::new(sink, &types);
+    pin_mut!(writer);
+    for row in rows {
+        writer.as_mut().write(&row.params()).await?;
+    }
+
+    Ok(writer.finish().await?)
+}
+
+pub(crate) fn copy_out<'a, T>(executor: Executor<'a>) -> BoxStream<'a, Result<T>>
+where
+    T: Model + Send + 'a,
+{
+    let rows = async move {
+        let conn = executor.get().await?;
+        let types = column_types::<T>(&conn).await?;
+
+        let stream = conn
+            .copy_out(&format!(
+                "COPY (SELECT {} FROM {}) TO STDOUT (FORMAT binary)",
+                T::COLUMNS.join(", "),
+                T::NAME
+            ))
+            .await?;
+
+        // the connection is held until the copy is drained
+        let rows = BinaryCopyOutStream::new(stream, &types).map(move |row| {
+            let _conn = &conn;
+            T::from_copy_row(&row?)
+        });
+
+        Ok::<_, anyhow::Error>(rows)
+    };
+
+    rows.try_flatten_stream().boxed()
+}
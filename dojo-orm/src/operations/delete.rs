@@ -1,4 +1,4 @@
-use crate::execution::Execution;
+use crate::execution::{Execution, Executor};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::DerefMut;
@@ -8,14 +8,14 @@ use crate::pool::*;
 use crate::types::ToSql;
 
 use crate::model::{Model, UpdateModel};
-use crate::predicates::WherePredicate;
+use crate::predicates::{IntoWherePredicate, WherePredicate};
 use crate::query_builder::{QueryBuilder, QueryType};
 
 pub struct DeleteOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
+    pub(crate) executor: Executor<'a>,
     pub(crate) predicates: Vec<WherePredicate<'a>>,
     pub(crate) _t: PhantomData<T>,
 }
@@ -24,8 +24,8 @@ impl<'a, T> DeleteOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub fn where_by(&'a mut self, predicate: WherePredicate<'a>) -> &'a mut Self {
-        self.predicates.push(predicate);
+    pub fn where_by(&'a mut self, predicate: impl IntoWherePredicate<'a, T>) -> &'a mut Self {
+        self.predicates.push(predicate.into_where_predicate());
         self
     }
 
@@ -38,7 +38,7 @@ where
             .is_returning(true)
             .build();
 
-        let execution = Execution::new(self.pool, &qb);
+        let execution = Execution::new(self.executor, &qb);
         execution.first_or_throw().await
     }
 }
@@ -1,21 +1,20 @@
 use std::fmt::Debug;
 
 use anyhow::Result;
-use bb8::Pool;
-use bb8_postgres::PostgresConnectionManager;
 use postgres_types::ToSql;
-use tokio_postgres::NoTls;
 
-use crate::execution::Execution;
-use crate::query_builder::{QueryBuilder, QueryType};
+use crate::execution::{Execution, Executor};
+use crate::operations::insert::InsertSource;
+use crate::operations::insert_from::InsertSelect;
+use crate::query_builder::{insert_batches, QueryBuilder, QueryType};
 use crate::Model;
 
 pub struct DoOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
-    pub(crate) data: &'a [&'a T],
+    pub(crate) executor: Executor<'a>,
+    pub(crate) source: InsertSource<'a, T>,
     pub(crate) target: &'a [&'a str],
     pub(crate) updates: &'a [(&'a str, &'a (dyn ToSql + Sync))],
 }
@@ -24,11 +23,11 @@ impl<'a, T> DoOperation<'a, T>
 where
     T: Model + Debug,
 {
-    fn build_query(&self, params: &'a [&'a (dyn ToSql + Sync)]) -> QueryBuilder {
+    fn build_query(&self, params: &'a [&'a (dyn ToSql + Sync)]) -> QueryBuilder<'_> {
         QueryBuilder::builder()
             .table_name(T::NAME)
             .columns(T::COLUMNS)
-            .params(&params)
+            .params(params)
             .ty(QueryType::Insert)
             .is_returning(true)
             .conflict_target(self.target)
@@ -37,37 +36,57 @@ where
             .build()
     }
 
-    pub async fn all(&self) -> Result<Vec<T>> {
-        let mut params = vec![];
-        for p in self.data {
-            params.extend(p.params());
-        }
+    async fn insert_select(&self, insert_select: InsertSelect<'a>) -> Result<Vec<T>> {
+        let select = insert_select
+            .source
+            .build_query(insert_select.select_columns);
+        let qb = QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(insert_select.columns)
+            .ty(QueryType::Insert)
+            .is_returning(true)
+            .returning(T::COLUMNS)
+            .conflict_target(self.target)
+            .conflict_update(self.updates)
+            .on_conflict(true)
+            .insert_select(&select)
+            .build();
 
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
+        let execution = Execution::new(self.executor, &qb);
         execution.all().await
     }
 
-    pub async fn first(&self) -> Result<Option<T>> {
+    pub async fn all(&self) -> Result<Vec<T>> {
+        let data = match self.source {
+            InsertSource::Values(data) => data,
+            InsertSource::Select(insert_select) => return self.insert_select(insert_select).await,
+        };
+
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
         let mut params = vec![];
-        for p in self.data {
+        for p in data {
             params.extend(p.params());
         }
 
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
-        execution.first().await
+        // every row binds as many params as the first
+        let row_len = data[0].params().len();
+        let qbs = insert_batches(&params, row_len, self.updates.len())
+            .map(|batch| self.build_query(batch))
+            .collect::<Vec<_>>();
+        self.executor.all_in_batches(&qbs).await
     }
 
-    pub async fn first_or_throw(&self) -> Result<T> {
-        let params = if let Some(data) = self.data.first() {
-            data.params()
-        } else {
-            return Err(anyhow::anyhow!("no data to insert"));
-        };
+    /// Inserts every row like [`DoOperation::all`] and returns the first.
+    pub async fn first(&self) -> Result<Option<T>> {
+        Ok(self.all().await?.into_iter().next())
+    }
 
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
-        execution.first_or_throw().await
+    pub async fn first_or_throw(&self) -> Result<T> {
+        self.first()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no data to insert"))
     }
 }
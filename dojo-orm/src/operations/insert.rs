@@ -1,21 +1,33 @@
 use std::fmt::Debug;
 
 use anyhow::Result;
-use bb8::Pool;
-use bb8_postgres::PostgresConnectionManager;
 use postgres_types::ToSql;
-use tokio_postgres::NoTls;
 
-use crate::execution::Execution;
+use crate::execution::Executor;
+use crate::operations::insert_from::InsertSelect;
 use crate::operations::on_conflict::OnConflictOperation;
-use crate::query_builder::{QueryBuilder, QueryType};
+use crate::query_builder::{insert_batches, QueryBuilder, QueryType};
 use crate::Model;
 
+/// The rows an insert writes: bound values, or the result of a select.
+pub(crate) enum InsertSource<'a, T> {
+    Values(&'a [&'a T]),
+    Select(InsertSelect<'a>),
+}
+
+impl<T> Clone for InsertSource<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for InsertSource<'_, T> {}
+
 pub struct InsertOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
+    pub(crate) executor: Executor<'a>,
     pub(crate) data: &'a [&'a T],
 }
 
@@ -23,11 +35,11 @@ impl<'a, T> InsertOperation<'a, T>
 where
     T: Model + Debug,
 {
-    fn build_query(&self, params: &'a [&'a (dyn ToSql + Sync)]) -> QueryBuilder {
+    fn build_query(&self, params: &'a [&'a (dyn ToSql + Sync)]) -> QueryBuilder<'_> {
         QueryBuilder::builder()
             .table_name(T::NAME)
             .columns(T::COLUMNS)
-            .params(&params)
+            .params(params)
             .ty(QueryType::Insert)
             .is_returning(true)
             .build()
@@ -35,8 +47,8 @@ where
 
     pub fn on_conflict(&self, target: &'a [&'a str]) -> OnConflictOperation<'a, T> {
         OnConflictOperation {
-            pool: self.pool,
-            data: self.data,
+            executor: self.executor,
+            source: InsertSource::Values(self.data),
             target,
         }
     }
@@ -51,32 +63,22 @@ where
             params.extend(data.params());
         }
 
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
-        execution.all().await
+        // every row binds as many params as the first
+        let row_len = self.data[0].params().len();
+        let qbs = insert_batches(&params, row_len, 0)
+            .map(|batch| self.build_query(batch))
+            .collect::<Vec<_>>();
+        self.executor.all_in_batches(&qbs).await
     }
 
+    /// Inserts every row like [`InsertOperation::all`] and returns the first.
     pub async fn first(&self) -> Result<Option<T>> {
-        let params = if let Some(data) = self.data.first() {
-            data.params()
-        } else {
-            return Ok(None);
-        };
-
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
-        execution.first().await
+        Ok(self.all().await?.into_iter().next())
     }
 
     pub async fn first_or_throw(&self) -> Result<T> {
-        let params = if let Some(data) = self.data.first() {
-            data.params()
-        } else {
-            return Err(anyhow::anyhow!("no data to insert"));
-        };
-
-        let qb = self.build_query(&params);
-        let execution = Execution::new(&self.pool, &qb);
-        execution.first_or_throw().await
+        self.first()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no data to insert"))
     }
 }
@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::execution::{Execution, Executor};
+use crate::operations::insert::InsertSource;
+use crate::operations::on_conflict::OnConflictOperation;
+use crate::operations::select::SelectSource;
+use crate::query_builder::{QueryBuilder, QueryType};
+use crate::Model;
+
+#[derive(Clone, Copy)]
+pub(crate) struct InsertSelect<'a> {
+    pub(crate) source: SelectSource<'a>,
+    pub(crate) columns: &'a [&'a str],
+    pub(crate) select_columns: &'a [&'a str],
+}
+
+/// `INSERT INTO ... SELECT`, created by [`crate::Database::insert_from`].
+pub struct InsertFromOperation<'a, T>
+where
+    T: Model + Debug,
+{
+    pub(crate) executor: Executor<'a>,
+    pub(crate) source: SelectSource<'a>,
+    pub(crate) columns: Vec<&'a str>,
+    pub(crate) select_columns: Vec<&'a str>,
+    pub(crate) _t: PhantomData<T>,
+}
+
+impl<'a, T> InsertFromOperation<'a, T>
+where
+    T: Model + Debug,
+{
+    /// Inserts each `(column, expr)` pair's select expression into the column,
+    /// instead of selecting every column of `T` by name. Columns left out get
+    /// their default.
+    pub fn columns(&'a mut self, mapping: &'a [(&'a str, &'a str)]) -> &'a mut Self {
+        self.columns = mapping.iter().map(|(column, _)| *column).collect();
+        self.select_columns = mapping.iter().map(|(_, expr)| *expr).collect();
+        self
+    }
+
+    fn insert_select(&'a self) -> InsertSelect<'a> {
+        InsertSelect {
+            source: self.source,
+            columns: &self.columns,
+            select_columns: &self.select_columns,
+        }
+    }
+
+    pub fn on_conflict(&'a self, target: &'a [&'a str]) -> OnConflictOperation<'a, T> {
+        OnConflictOperation {
+            executor: self.executor,
+            source: InsertSource::Select(self.insert_select()),
+            target,
+        }
+    }
+
+    pub async fn all(&'a self) -> Result<Vec<T>> {
+        let insert_select = self.insert_select();
+        let select = insert_select
+            .source
+            .build_query(insert_select.select_columns);
+        let qb = QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(insert_select.columns)
+            .ty(QueryType::Insert)
+            .is_returning(true)
+            .returning(T::COLUMNS)
+            .insert_select(&select)
+            .build();
+
+        let execution = Execution::new(self.executor, &qb);
+        execution.all().await
+    }
+}
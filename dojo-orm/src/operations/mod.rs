@@ -1,12 +1,15 @@
 pub use delete::*;
 pub use insert::*;
 pub use insert::*;
+pub use insert_from::*;
 pub use select::*;
 pub use update::*;
 
+pub(crate) mod copy;
 mod delete;
 mod r#do;
 mod insert;
+mod insert_from;
 mod on_conflict;
 mod select;
 mod update;
@@ -1,18 +1,17 @@
+use crate::execution::Executor;
 use crate::Model;
-use bb8::Pool;
-use bb8_postgres::PostgresConnectionManager;
 use postgres_types::ToSql;
 use std::fmt::Debug;
-use tokio_postgres::NoTls;
 
+use crate::operations::insert::InsertSource;
 use crate::operations::r#do::DoOperation;
 
 pub struct OnConflictOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
-    pub(crate) data: &'a [&'a T],
+    pub(crate) executor: Executor<'a>,
+    pub(crate) source: InsertSource<'a, T>,
     pub(crate) target: &'a [&'a str],
 }
 
@@ -22,8 +21,8 @@ where
 {
     pub fn do_nothing(&self) -> DoOperation<'a, T> {
         DoOperation {
-            pool: self.pool,
-            data: self.data,
+            executor: self.executor,
+            source: self.source,
             target: self.target,
             updates: &[],
         }
@@ -34,8 +33,8 @@ where
         updates: &'a [(&'a str, &'a (dyn ToSql + Sync))],
     ) -> DoOperation<'a, T> {
         DoOperation {
-            pool: self.pool,
-            data: self.data,
+            executor: self.executor,
+            source: self.source,
             target: self.target,
             updates,
         }
@@ -1,38 +1,161 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::future::IntoFuture;
 use std::marker::PhantomData;
 
 use crate::execution;
-use crate::execution::Execution;
-use tracing::debug;
+use crate::execution::{Execution, Executor};
+use tracing::{debug, warn};
 
 use crate::model::Model;
-use crate::order_by::OrderPredicate;
-use crate::pagination::{Cursor, DefaultSortKeys, Pagination};
+use crate::order_by::{Direction, OrderPredicate};
+use crate::pagination::{
+    Cursor, CursorCodec, CursorError, CursorOptions, DefaultSortKeys, OffsetPagination, Pagination,
+    TotalCount,
+};
 use crate::pool::*;
-use crate::predicates::{Expr, ExprValueType, WherePredicate};
-use crate::query_builder::{QueryBuilder, QueryType};
+use crate::predicates::{Expr, ExprValueType, IntoWherePredicate, WherePredicate};
+use crate::query_builder::{Cte, Distinct, LockStrength, LockWait, QueryBuilder, QueryType};
+use crate::raw::RawSql;
 use crate::types::ToSql;
+use crate::MaxRows;
 use anyhow::Result;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, LocalBoxStream};
+use futures::{FutureExt, SinkExt, StreamExt};
+
+pub const DEFAULT_FETCH_SIZE: i32 = 100;
+
+// the rows of a distinct select, for counting and paging over them
+const DISTINCT_ROWS: &str = "dojo_distinct_rows";
 
 pub struct SelectOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
+    pub(crate) executor: Executor<'a>,
     pub(crate) params: Vec<&'a (dyn ToSql + Sync)>,
     pub(crate) columns: &'a [&'a str],
+    pub(crate) ctes: Vec<Cte<'a>>,
     pub(crate) order_by: Vec<OrderPredicate<'a>>,
     pub(crate) predicates: Vec<WherePredicate<'a>>,
-    pub(crate) _t: PhantomData<T>,
+    pub(crate) limit: Option<i64>,
+    pub(crate) offset: Option<i64>,
+    pub(crate) distinct: Option<Distinct<'a>>,
+    pub(crate) max_rows: Option<MaxRows>,
+    pub(crate) cursor_codec: &'a CursorCodec,
+    pub(crate) fetch_size: i32,
+    pub(crate) lock: Option<LockStrength>,
+    pub(crate) lock_wait: LockWait,
+    // fn() -> T keeps the operation Send and Sync whatever T is
+    pub(crate) _t: PhantomData<fn() -> T>,
+}
+
+/// The parts of a [`SelectOperation`] an `INSERT ... SELECT` reads from,
+/// without the model type of the selected table.
+#[derive(Clone, Copy)]
+pub(crate) struct SelectSource<'a> {
+    table_name: &'a str,
+    params: &'a [&'a (dyn ToSql + Sync)],
+    ctes: &'a [Cte<'a>],
+    predicates: &'a [WherePredicate<'a>],
+    order_by: &'a [OrderPredicate<'a>],
+    limit: Option<i64>,
+    offset: Option<i64>,
+    distinct: Option<Distinct<'a>>,
+    lock: Option<LockStrength>,
+    lock_wait: LockWait,
+}
+
+impl<'a> SelectSource<'a> {
+    pub(crate) fn build_query(&self, columns: &'a [&'a str]) -> QueryBuilder<'a> {
+        QueryBuilder::builder()
+            .table_name(self.table_name)
+            .columns(columns)
+            .params(self.params)
+            .ctes(self.ctes)
+            .where_predicates(self.predicates)
+            .order_by_predicates(self.order_by)
+            .distinct(self.distinct)
+            .ty(QueryType::Select)
+            .limit(self.limit)
+            .offset(self.offset)
+            .lock(self.lock)
+            .lock_wait(self.lock_wait)
+            .build()
+    }
+
+    // numbered from $1 like any other fragment, the outer statement shifts
+    // the placeholders when the fragment is nested
+    pub(crate) fn build_subquery(&self, columns: &'a [&'a str]) -> Result<RawSql<'a>> {
+        let mut qb = self.build_query(columns);
+        if qb.lock.is_none() {
+            qb.lock_wait = LockWait::Wait;
+        }
+
+        let (sql, params) = qb.build_select_sql()?;
+        Ok(RawSql::with_params(sql, params))
+    }
 }
 
 impl<'a, T> SelectOperation<'a, T>
 where
     T: Model + Debug,
 {
-    pub fn where_by(&'a mut self, predicate: WherePredicate<'a>) -> &'a mut Self {
-        self.predicates.push(predicate);
+    pub(crate) fn new(
+        executor: Executor<'a>,
+        max_rows: Option<MaxRows>,
+        cursor_codec: &'a CursorCodec,
+    ) -> Self {
+        Self {
+            executor,
+            columns: T::COLUMNS,
+            params: vec![],
+            ctes: vec![],
+            predicates: vec![],
+            order_by: vec![],
+            limit: None,
+            offset: None,
+            distinct: None,
+            max_rows,
+            cursor_codec,
+            fetch_size: DEFAULT_FETCH_SIZE,
+            lock: None,
+            lock_wait: LockWait::Wait,
+            _t: PhantomData,
+        }
+    }
+
+    pub fn where_by(&'a mut self, predicate: impl IntoWherePredicate<'a, T>) -> &'a mut Self {
+        self.predicates.push(predicate.into_where_predicate());
+        self
+    }
+
+    /// Adds `WITH name AS (query)`, e.g. with the query of another select's
+    /// [`SelectOperation::subquery`]. Selecting a model named `name` reads
+    /// from it.
+    pub fn with(&'a mut self, name: &'a str, query: RawSql<'a>) -> &'a mut Self {
+        self.ctes.push(Cte {
+            name,
+            query,
+            recursive: false,
+        });
+        self
+    }
+
+    /// Adds `WITH RECURSIVE name AS (initial UNION ALL recursive)`, where
+    /// `recursive` refers to `name` for the rows found so far.
+    pub fn with_recursive(
+        &'a mut self,
+        name: &'a str,
+        initial: RawSql<'a>,
+        recursive: RawSql<'a>,
+    ) -> &'a mut Self {
+        self.ctes.push(Cte {
+            name,
+            query: initial.push(" UNION ALL ").append(recursive),
+            recursive: true,
+        });
         self
     }
 
@@ -41,6 +164,86 @@ where
         self
     }
 
+    pub fn limit(&'a mut self, limit: i64) -> &'a mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(&'a mut self, offset: i64) -> &'a mut Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn distinct(&'a mut self) -> &'a mut Self {
+        self.distinct = Some(Distinct::Rows);
+        self
+    }
+
+    /// Keeps the first row of each group of rows with equal `columns`, which
+    /// have to lead the order by, e.g. the latest order per user with
+    /// `distinct_on(&["user_id"])`, `order_by(asc("user_id"))` and
+    /// `order_by(desc("created_at"))`.
+    pub fn distinct_on(&'a mut self, columns: &'a [&'a str]) -> &'a mut Self {
+        self.distinct = Some(Distinct::On(columns));
+        self
+    }
+
+    pub fn fetch_size(&'a mut self, fetch_size: i32) -> &'a mut Self {
+        self.fetch_size = fetch_size;
+        self
+    }
+
+    /// Locks the selected rows until the end of the transaction; outside of
+    /// one they are only locked for the statement itself.
+    pub fn for_update(&'a mut self) -> &'a mut Self {
+        self.lock = Some(LockStrength::Update);
+        self
+    }
+
+    pub fn for_no_key_update(&'a mut self) -> &'a mut Self {
+        self.lock = Some(LockStrength::NoKeyUpdate);
+        self
+    }
+
+    pub fn for_share(&'a mut self) -> &'a mut Self {
+        self.lock = Some(LockStrength::Share);
+        self
+    }
+
+    /// Fails instead of waiting for rows locked by another transaction.
+    pub fn nowait(&'a mut self) -> &'a mut Self {
+        self.lock_wait = LockWait::NoWait;
+        self
+    }
+
+    /// Leaves out rows locked by another transaction.
+    pub fn skip_locked(&'a mut self) -> &'a mut Self {
+        self.lock_wait = LockWait::SkipLocked;
+        self
+    }
+
+    pub(crate) fn source(&'a self) -> SelectSource<'a> {
+        SelectSource {
+            table_name: T::NAME,
+            params: &self.params,
+            ctes: &self.ctes,
+            predicates: &self.predicates,
+            order_by: &self.order_by,
+            limit: self.limit,
+            offset: self.offset,
+            distinct: self.distinct,
+            lock: self.lock,
+            lock_wait: self.lock_wait,
+        }
+    }
+
+    /// Compiles the select of `columns` for nesting in another statement,
+    /// e.g. with [`crate::predicates::in_subquery`]. Fails like the select
+    /// would, e.g. on a `distinct_on` not matching the order by.
+    pub fn subquery(&'a self, columns: &'a [&'a str]) -> Result<RawSql<'a>> {
+        self.source().build_subquery(columns)
+    }
+
     pub async fn cursor(
         &'a self,
         first: Option<i64>,
@@ -48,20 +251,196 @@ where
         last: Option<i64>,
         before: Option<Cursor>,
     ) -> Result<Pagination<T>> {
+        self.cursor_with_options(first, after, last, before, CursorOptions::default())
+            .await
+    }
+
+    pub async fn cursor_with_options(
+        &'a self,
+        first: Option<i64>,
+        after: Option<Cursor>,
+        last: Option<i64>,
+        before: Option<Cursor>,
+        options: CursorOptions,
+    ) -> Result<Pagination<T>> {
+        // cursors decoded without a key, e.g. by the async-graphql scalar,
+        // can't be told apart from forged ones
+        let is_unverified = [&after, &before]
+            .into_iter()
+            .flatten()
+            .any(|cursor| cursor.unverified);
+        if self.cursor_codec.is_keyed() && is_unverified {
+            return Err(CursorError::MissingSignature.into());
+        }
+
+        let (first, last) = match (first, last) {
+            (None, None) if before.is_some() => (None, Some(options.page_size)),
+            (None, None) => (Some(options.page_size), None),
+            sizes => sizes,
+        };
+        let (first, last) = match self.max_rows {
+            Some(max_rows) => (
+                self.check_page_size(first, max_rows)?,
+                self.check_page_size(last, max_rows)?,
+            ),
+            None => (first, last),
+        };
+
+        // DISTINCT ON keeps the first row of each group in the order of its
+        // own select, so the groups are picked by a subquery sorted forward,
+        // and the cursor and the direction of the page only apply to its rows
+        let forward;
+        let forward_order_by;
+        let mut ctes = self.ctes.clone();
+        let (table_name, params, predicates, distinct) = match self.distinct {
+            Some(Distinct::On(_)) => {
+                forward = QueryBuilder::builder()
+                    .table_name(T::NAME)
+                    .default_keys(T::sort_keys())
+                    .order_by_predicates(&self.order_by)
+                    .ty(QueryType::Paging)
+                    .build();
+                forward_order_by = forward
+                    .build_sort_keys()?
+                    .iter()
+                    .map(|key| match key.direction {
+                        Direction::Desc => OrderPredicate::Desc(key.column),
+                        _ => OrderPredicate::Asc(key.column),
+                    })
+                    .collect::<Vec<_>>();
+
+                let rows = QueryBuilder::builder()
+                    .table_name(T::NAME)
+                    .columns(self.columns)
+                    .params(&self.params)
+                    .where_predicates(&self.predicates)
+                    .order_by_predicates(&forward_order_by)
+                    .distinct(self.distinct)
+                    .ty(QueryType::Select)
+                    .lock(self.lock)
+                    .lock_wait(self.lock_wait)
+                    .build();
+                let (sql, params) = rows.build_select_sql()?;
+                ctes.push(Cte {
+                    name: DISTINCT_ROWS,
+                    query: RawSql::with_params(sql, params),
+                    recursive: false,
+                });
+
+                (DISTINCT_ROWS, &[][..], &[][..], None)
+            }
+            _ => (
+                T::NAME,
+                &self.params[..],
+                &self.predicates[..],
+                self.distinct,
+            ),
+        };
+
         let qb = QueryBuilder::builder()
-            .table_name(T::NAME)
+            .table_name(table_name)
             .default_keys(T::sort_keys())
+            .nullable_columns(T::NULLABLE_COLUMNS)
             .columns(self.columns)
-            .params(&self.params)
-            .where_predicates(&self.predicates)
+            .params(params)
+            .ctes(&ctes)
+            .where_predicates(predicates)
+            .order_by_predicates(&self.order_by)
+            .distinct(distinct)
             .first(first)
             .after(&after)
             .last(last)
             .before(&before)
             .ty(QueryType::Paging)
+            .lock(self.lock)
+            .lock_wait(self.lock_wait)
+            .build();
+
+        let sort_keys = qb
+            .build_sort_keys()?
+            .iter()
+            .map(|key| key.column.to_string())
+            .collect::<Vec<_>>();
+        // the page's cursors would miss the value and never match the keys
+        if let Some(key) = sort_keys.iter().find(|key| !T::has_value(key)) {
+            return Err(anyhow::anyhow!(
+                "can't page by {}, its values can't be held in a cursor",
+                key
+            ));
+        }
+
+        // inside a transaction both queries share its connection, and with it
+        // the transaction's view of the rows
+        let execution = Execution::new(self.executor, &qb);
+        let query_all_fut = execution.all::<T>();
+        let query_count_fut = self.total_count(options.total_count);
+
+        let (records, count) = tokio::try_join!(query_all_fut, query_count_fut)?;
+
+        debug!(?records);
+        debug!(?count);
+
+        Ok(
+            Pagination::new(records, first, after, last, before, count, sort_keys)
+                .with_codec(self.cursor_codec.clone()),
+        )
+    }
+
+    /// Returns the 1-based `page` of `per_page` rows, which replace any limit
+    /// or offset of the select, so setting those is an error.
+    pub async fn paginate(&'a self, page: i64, per_page: i64) -> Result<OffsetPagination<T>> {
+        if page < 1 {
+            return Err(anyhow::anyhow!("page must be greater than 0"));
+        }
+        if per_page < 1 {
+            return Err(anyhow::anyhow!("per_page must be greater than 0"));
+        }
+        // the page decides both, a limit or offset of the select would be lost
+        if self.limit.is_some() || self.offset.is_some() {
+            return Err(anyhow::anyhow!(
+                "paginate can't be combined with limit or offset"
+            ));
+        }
+
+        let per_page = match self.max_rows {
+            Some(max_rows) => self
+                .check_page_size(Some(per_page), max_rows)?
+                .unwrap_or(per_page),
+            None => per_page,
+        };
+
+        // without an explicit order the pages wouldn't be stable, fall back to
+        // the model sort keys
+        let sort_keys = T::sort_keys();
+        let order_by = if self.order_by.is_empty() {
+            sort_keys
+                .iter()
+                .map(|key| OrderPredicate::Asc(key))
+                .collect()
+        } else {
+            self.order_by.clone()
+        };
+
+        let offset = (page - 1)
+            .checked_mul(per_page)
+            .ok_or_else(|| anyhow::anyhow!("page {} is out of range", page))?;
+
+        let qb = QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(self.columns)
+            .params(&self.params)
+            .ctes(&self.ctes)
+            .where_predicates(&self.predicates)
+            .order_by_predicates(&order_by)
+            .distinct(self.distinct)
+            .ty(QueryType::Select)
+            .limit(Some(per_page))
+            .offset(Some(offset))
+            .lock(self.lock)
+            .lock_wait(self.lock_wait)
             .build();
 
-        let execution = Execution::new(self.pool, &qb);
+        let execution = Execution::new(self.executor, &qb);
         let query_all_fut = execution.all::<T>();
         let query_count_fut = self.count();
 
@@ -70,7 +449,42 @@ where
         debug!(?records);
         debug!(?count);
 
-        Ok(Pagination::new(records, first, after, last, before, count))
+        Ok(OffsetPagination::new(records, page, per_page, count))
+    }
+
+    fn check_page_size(&self, size: Option<i64>, max_rows: MaxRows) -> Result<Option<i64>> {
+        match size {
+            Some(size) if size > max_rows.rows() => match max_rows {
+                MaxRows::Error(rows) => Err(anyhow::anyhow!(
+                    "page size {} on {} exceeds max rows {}",
+                    size,
+                    T::NAME,
+                    rows
+                )),
+                MaxRows::Warn(rows) => {
+                    warn!(size, rows, table = T::NAME, "page size clamped to max rows");
+                    Ok(Some(rows))
+                }
+            },
+            size => Ok(size),
+        }
+    }
+
+    fn build_query(&'a self) -> QueryBuilder<'a> {
+        QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(self.columns)
+            .params(&self.params)
+            .ctes(&self.ctes)
+            .where_predicates(&self.predicates)
+            .order_by_predicates(&self.order_by)
+            .distinct(self.distinct)
+            .ty(QueryType::Select)
+            .limit(self.limit)
+            .offset(self.offset)
+            .lock(self.lock)
+            .lock_wait(self.lock_wait)
+            .build()
     }
 
     fn build_query_by_limit(&'a self, limit: i64) -> QueryBuilder<'a> {
@@ -78,23 +492,33 @@ where
             .table_name(T::NAME)
             .columns(self.columns)
             .params(&self.params)
+            .ctes(&self.ctes)
             .where_predicates(&self.predicates)
             .order_by_predicates(&self.order_by)
+            .distinct(self.distinct)
             .ty(QueryType::Select)
-            .limit(limit)
+            .limit(Some(limit))
+            .offset(self.offset)
+            .lock(self.lock)
+            .lock_wait(self.lock_wait)
             .build()
     }
 
     pub async fn count(&'a self) -> Result<i64> {
+        if let Some(distinct) = self.distinct {
+            return self.count_distinct(distinct).await;
+        }
+
         let qb = QueryBuilder::builder()
             .table_name(T::NAME)
             .columns(&["COUNT(*) as count"])
             .params(&self.params)
+            .ctes(&self.ctes)
             .where_predicates(&self.predicates)
             .ty(QueryType::Select)
             .build();
 
-        let execution = Execution::new(self.pool, &qb);
+        let execution = Execution::new(self.executor, &qb);
         let row = execution.query_one().await?;
 
         let count = row.get("count");
@@ -102,24 +526,166 @@ where
         Ok(count)
     }
 
-    pub async fn limit(&'a self, limit: i64) -> Result<Vec<T>> {
-        let qb = self.build_query_by_limit(limit);
+    // counts the distinct values of the distinct columns, which is the number
+    // of rows the select returns without a limit
+    async fn count_distinct(&'a self, distinct: Distinct<'a>) -> Result<i64> {
+        let columns = match distinct {
+            Distinct::Rows => self.columns,
+            Distinct::On(columns) => columns,
+        };
+        let rows = QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(columns)
+            .params(&self.params)
+            .where_predicates(&self.predicates)
+            .distinct(Some(Distinct::Rows))
+            .ty(QueryType::Select)
+            .build();
+        let (sql, params) = rows.build_select_sql()?;
+
+        let mut ctes = self.ctes.clone();
+        ctes.push(Cte {
+            name: DISTINCT_ROWS,
+            query: RawSql::with_params(sql, params),
+            recursive: false,
+        });
+        let qb = QueryBuilder::builder()
+            .table_name(DISTINCT_ROWS)
+            .columns(&["COUNT(*) as count"])
+            .ctes(&ctes)
+            .ty(QueryType::Select)
+            .build();
+
+        let execution = Execution::new(self.executor, &qb);
+        let row = execution.query_one().await?;
+
+        let count = row.get("count");
+
+        Ok(count)
+    }
+
+    async fn total_count(&'a self, total_count: TotalCount) -> Result<Option<i64>> {
+        match total_count {
+            TotalCount::Exact => self.count().await.map(Some),
+            TotalCount::None => Ok(None),
+            TotalCount::Estimated => self.estimate_count().await.map(Some),
+        }
+    }
+
+    async fn estimate_count(&'a self) -> Result<i64> {
+        // a cte named like a table shadows it
+        if self.predicates.is_empty() && self.ctes.is_empty() && self.distinct.is_none() {
+            let conn = self.executor.get().await?;
+            // reltuples is -1 until the table has been analyzed
+            let row = conn
+                .query_opt(
+                    "SELECT reltuples::bigint AS count FROM pg_class WHERE oid = to_regclass($1)",
+                    &[&T::NAME],
+                )
+                .await?;
+            if let Some(count) = row.map(|row| row.get::<_, i64>("count")) {
+                if count >= 0 {
+                    return Ok(count);
+                }
+            }
+        }
+
+        // distinct rows depend on the selected columns
+        let columns = if self.distinct.is_some() {
+            self.columns
+        } else {
+            &["1"]
+        };
+        let qb = QueryBuilder::builder()
+            .table_name(T::NAME)
+            .columns(columns)
+            .params(&self.params)
+            .ctes(&self.ctes)
+            .where_predicates(&self.predicates)
+            .distinct(self.distinct)
+            .ty(QueryType::Select)
+            .build();
 
-        let execution = Execution::new(self.pool, &qb);
-        execution.all().await
+        let execution = Execution::new(self.executor, &qb);
+        execution.estimate_rows().await
     }
 
     pub async fn first(&'a self) -> Result<Option<T>> {
         let qb = self.build_query_by_limit(1);
 
-        let execution = Execution::new(self.pool, &qb);
+        let execution = Execution::new(self.executor, &qb);
         execution.first().await
     }
 
     pub async fn all(&'a self) -> Result<Vec<T>> {
-        let qb = self.build_query_by_limit(500);
+        let Some(max_rows) = self.max_rows else {
+            let qb = self.build_query();
+            let execution = Execution::new(self.executor, &qb);
+            return execution.all().await;
+        };
+
+        // an explicit limit under the guard can never be truncated by it
+        if let Some(limit) = self.limit.filter(|limit| *limit <= max_rows.rows()) {
+            let qb = self.build_query_by_limit(limit);
+            let execution = Execution::new(self.executor, &qb);
+            return execution.all().await;
+        }
+
+        // fetch one extra row to tell a full result apart from a truncated one
+        let qb = self.build_query_by_limit(max_rows.rows() + 1);
+        let execution = Execution::new(self.executor, &qb);
+        let mut records = execution.all().await?;
+        if records.len() as i64 > max_rows.rows() {
+            match max_rows {
+                MaxRows::Error(rows) => {
+                    return Err(anyhow::anyhow!(
+                        "select on {} returned more than max rows {}",
+                        T::NAME,
+                        rows
+                    ));
+                }
+                MaxRows::Warn(rows) => {
+                    warn!(rows, table = T::NAME, "select truncated to max rows");
+                    records.truncate(rows as usize);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+
+    pub fn stream(&'a self) -> BoxStream<'a, Result<T>>
+    where
+        T: Send,
+    {
+        let capacity = self.fetch_size.max(1) as usize;
+        let (sender, receiver) = futures::channel::mpsc::channel(capacity);
+
+        let producer = async move {
+            let qb = self.build_query();
+            let execution = Execution::new(self.executor, &qb);
+            let mut errors = sender.clone();
+            if let Err(e) = execution.stream(self.fetch_size, sender).await {
+                let _ = errors.send(Err(e)).await;
+            }
+        };
+
+        // the producer never yields items, it only has to be polled alongside
+        // the receiver so rows keep flowing through the channel
+        let producer = producer.into_stream().filter_map(|_| async { None });
+        futures::stream::select(producer, receiver).boxed()
+    }
+}
+
+impl<'a, T> IntoFuture for &'a mut SelectOperation<'a, T>
+where
+    T: Model + Debug + Send,
+{
+    type Output = Result<Vec<T>>;
+    type IntoFuture = BoxFuture<'a, Self::Output>;
 
-        let execution = Execution::new(self.pool, &qb);
-        execution.all().await
+    fn into_future(self) -> Self::IntoFuture {
+        let op: &'a SelectOperation<'a, T> = self;
+        op.all().boxed()
     }
 }
@@ -1,4 +1,4 @@
-use crate::execution::Execution;
+use crate::execution::{Execution, Executor};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::DerefMut;
@@ -7,18 +7,21 @@ use tracing::debug;
 use crate::pool::*;
 use crate::types::ToSql;
 
+use crate::column::ColumnRef;
 use crate::model::{Model, UpdateModel};
-use crate::predicates::WherePredicate;
+use crate::predicates::{path_literal, IntoWherePredicate, WherePredicate};
 use crate::query_builder::{QueryBuilder, QueryType};
+use crate::raw::RawSql;
 
 pub struct UpdateOperation<'a, T, U>
 where
     T: Model + Debug,
     U: UpdateModel,
 {
-    pub(crate) pool: &'a Pool<PostgresConnectionManager<NoTls>>,
+    pub(crate) executor: Executor<'a>,
     pub(crate) columns: Vec<&'a str>,
     pub(crate) params: Vec<&'a (dyn ToSql + Sync)>,
+    pub(crate) set_exprs: Vec<(&'a str, RawSql<'a>)>,
     pub(crate) predicates: Vec<WherePredicate<'a>>,
     pub(crate) _t: PhantomData<T>,
     pub(crate) _u: PhantomData<U>,
@@ -29,23 +32,112 @@ where
     T: Model + Debug,
     U: UpdateModel,
 {
-    pub fn where_by(&'a mut self, predicate: WherePredicate<'a>) -> &'a mut Self {
-        self.predicates.push(predicate);
+    pub fn where_by(&'a mut self, predicate: impl IntoWherePredicate<'a, T>) -> &'a mut Self {
+        self.predicates.push(predicate.into_where_predicate());
+        self
+    }
+
+    /// `column = jsonb_set(column, path, value)`
+    pub fn jsonb_set<V>(
+        &'a mut self,
+        column: impl ColumnRef<'a>,
+        path: &[&str],
+        value: &'a V,
+    ) -> &'a mut Self
+    where
+        V: ToSql + Sync,
+    {
+        let path = path_literal(path);
+        self.push_set_expr(column.column_name(), |current| {
+            RawSql::new("jsonb_set(")
+                .append(current)
+                .push(&format!(", {}, ", path))
+                .bind(value)
+                .push("::jsonb)")
+        })
+    }
+
+    /// `column = column || patch`, merging the top level keys of `patch`.
+    pub fn jsonb_merge<V>(&'a mut self, column: impl ColumnRef<'a>, patch: &'a V) -> &'a mut Self
+    where
+        V: ToSql + Sync,
+    {
+        self.push_set_expr(column.column_name(), |current| {
+            RawSql::new("(")
+                .append(current)
+                .push(" || ")
+                .bind(patch)
+                .push("::jsonb)")
+        })
+    }
+
+    /// `column = column #- path`
+    pub fn jsonb_remove(&'a mut self, column: impl ColumnRef<'a>, path: &[&str]) -> &'a mut Self {
+        let path = path_literal(path);
+        self.push_set_expr(column.column_name(), |current| {
+            RawSql::new("(")
+                .append(current)
+                .push(&format!(" #- {})", path))
+        })
+    }
+
+    /// Appends `value` to the array at `path`, or to the column itself when
+    /// `path` is empty.
+    pub fn jsonb_array_append<V>(
+        &'a mut self,
+        column: impl ColumnRef<'a>,
+        path: &[&str],
+        value: &'a V,
+    ) -> &'a mut Self
+    where
+        V: ToSql + Sync,
+    {
+        let path = path_literal(&[path, &["-1"]].concat());
+        self.push_set_expr(column.column_name(), |current| {
+            RawSql::new("jsonb_insert(")
+                .append(current)
+                .push(&format!(", {}, ", path))
+                .bind(value)
+                .push("::jsonb, true)")
+        })
+    }
+
+    // Several updates of the same column are nested into one expression.
+    fn push_set_expr(
+        &'a mut self,
+        column: &'a str,
+        f: impl FnOnce(RawSql<'a>) -> RawSql<'a>,
+    ) -> &'a mut Self {
+        match self.set_exprs.iter_mut().find(|(c, _)| *c == column) {
+            Some((_, expr)) => *expr = f(std::mem::take(expr)),
+            None => self.set_exprs.push((column, f(RawSql::new(column)))),
+        }
         self
     }
 
     pub async fn exec(&'a self) -> anyhow::Result<T> {
-        let qb = QueryBuilder::builder()
+        let qb = self.build_query();
+        let execution = Execution::new(self.executor, &qb);
+        execution.first_or_throw().await
+    }
+
+    /// Like [`UpdateOperation::exec`], with `None` when no row matched.
+    pub async fn first(&'a self) -> anyhow::Result<Option<T>> {
+        let qb = self.build_query();
+        let execution = Execution::new(self.executor, &qb);
+        execution.first().await
+    }
+
+    fn build_query(&'a self) -> QueryBuilder<'a> {
+        QueryBuilder::builder()
             .table_name(T::NAME)
             .columns(&self.columns)
             .params(&self.params)
+            .set_exprs(&self.set_exprs)
             .where_predicates(&self.predicates)
             .ty(QueryType::Update)
             .is_returning(true)
             .returning(T::COLUMNS)
-            .build();
-
-        let execution = Execution::new(self.pool, &qb);
-        execution.first_or_throw().await
+            .build()
     }
 }
@@ -1,3 +1,4 @@
+use crate::column::ColumnRef;
 use crate::types::Vector;
 use async_graphql::Enum;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,16 @@ pub enum Direction {
     Nearest,
 }
 
+impl Direction {
+    pub fn reverse(&self) -> Self {
+        match self {
+            Direction::Asc => Direction::Desc,
+            Direction::Desc => Direction::Asc,
+            Direction::Nearest => Direction::Nearest,
+        }
+    }
+}
+
 impl Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -33,6 +44,16 @@ pub enum OrderPredicate<'a> {
     Nearest(&'a str, &'a Vector),
 }
 
+impl<'a> OrderPredicate<'a> {
+    pub fn column(&self) -> &'a str {
+        match self {
+            OrderPredicate::Asc(column) => column,
+            OrderPredicate::Desc(column) => column,
+            OrderPredicate::Nearest(column, _) => column,
+        }
+    }
+}
+
 impl<'a> From<(&'a String, Direction)> for OrderPredicate<'a> {
     fn from((column, direction): (&'a String, Direction)) -> Self {
         match direction {
@@ -43,14 +64,32 @@ impl<'a> From<(&'a String, Direction)> for OrderPredicate<'a> {
     }
 }
 
-pub fn asc(column: &str) -> OrderPredicate {
-    OrderPredicate::Asc(column)
+/// A column a cursor page is sorted by, in the direction the query scans it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SortKey<'a> {
+    pub column: &'a str,
+    pub direction: Direction,
+    pub nullable: bool,
+}
+
+impl<'a> SortKey<'a> {
+    pub fn new(column: &'a str, direction: Direction) -> Self {
+        Self {
+            column,
+            direction,
+            nullable: false,
+        }
+    }
+}
+
+pub fn asc<'a>(column: impl ColumnRef<'a>) -> OrderPredicate<'a> {
+    OrderPredicate::Asc(column.column_name())
 }
 
-pub fn desc(column: &str) -> OrderPredicate {
-    OrderPredicate::Desc(column)
+pub fn desc<'a>(column: impl ColumnRef<'a>) -> OrderPredicate<'a> {
+    OrderPredicate::Desc(column.column_name())
 }
 
-pub fn nearest<'a>(column: &'a str, vector: &'a Vector) -> OrderPredicate<'a> {
-    OrderPredicate::Nearest(column, vector)
+pub fn nearest<'a>(column: impl ColumnRef<'a>, vector: &'a Vector) -> OrderPredicate<'a> {
+    OrderPredicate::Nearest(column.column_name(), vector)
 }
@@ -1,11 +1,17 @@
-use crate::pagination::{Cursor, Pagination};
+use crate::pagination::{Cursor, CursorError, OffsetPagination, Pagination};
 use crate::Model;
 use async_graphql::connection::{Connection, CursorType, Edge};
 use async_graphql::{
-    InputValueError, InputValueResult, OutputType, Scalar, ScalarType, SimpleObject, Value,
+    InputValueError, InputValueResult, OutputType, Scalar, ScalarType, SimpleObject, TypeName,
+    Value,
 };
+use std::borrow::Cow;
 use std::fmt::Debug;
+use tracing::warn;
 
+// Unsigned cursors, a database with a keyed `CursorCodec` refuses to page from
+// them. Convert its pages to a `Connection<String, ..>` and decode the
+// arguments with `Database::decode_cursor` instead.
 #[Scalar]
 impl ScalarType for Cursor {
     fn parse(value: Value) -> InputValueResult<Self> {
@@ -19,26 +25,26 @@ impl ScalarType for Cursor {
     }
 
     fn to_value(&self) -> Value {
-        let value = self.encode();
-        Value::String(value)
+        self.encode().map(Value::String).unwrap_or(Value::Null)
     }
 }
 
 impl CursorType for Cursor {
-    type Error = anyhow::Error;
+    type Error = CursorError;
 
     fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
         Self::decode(s)
     }
 
     fn encode_cursor(&self) -> String {
-        self.encode()
+        // the trait can't fail, and bincode only fails on unsized sequences
+        self.encode().unwrap_or_default()
     }
 }
 
 #[derive(SimpleObject, Debug, Clone)]
 pub struct AdditionalFields {
-    pub total_nodes: i64,
+    pub total_nodes: Option<i64>,
 }
 
 impl<T> From<Pagination<T>> for Connection<Cursor, T, AdditionalFields>
@@ -46,6 +52,10 @@ where
     T: OutputType + Model + Debug,
 {
     fn from(value: Pagination<T>) -> Self {
+        if value.codec.is_keyed() {
+            warn!("cursors of a keyed database are encoded unsigned, use Connection<String, ..>");
+        }
+
         let mut connection = Connection::with_additional_fields(
             value.has_previous,
             value.has_next,
@@ -54,12 +64,81 @@ where
             },
         );
 
+        let sort_keys = value.sort_keys;
         connection.edges = value
             .items
             .into_iter()
-            .map(|item| Edge::new(item.cursor(), item))
+            .map(|item| Edge::new(item.cursor_by(&sort_keys), item))
             .collect::<Vec<_>>();
 
         connection
     }
 }
+
+impl<T> TryFrom<Pagination<T>> for Connection<String, T, AdditionalFields>
+where
+    T: OutputType + Model + Debug,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(value: Pagination<T>) -> Result<Self, Self::Error> {
+        let mut connection = Connection::with_additional_fields(
+            value.has_previous,
+            value.has_next,
+            AdditionalFields {
+                total_nodes: value.total_nodes,
+            },
+        );
+
+        for item in value.items {
+            let cursor = value.codec.encode(&item.cursor_by(&value.sort_keys))?;
+            connection.edges.push(Edge::new(cursor, item));
+        }
+
+        Ok(connection)
+    }
+}
+
+#[derive(SimpleObject, Debug, Clone)]
+pub struct OffsetPageInfo {
+    pub page: i64,
+    pub per_page: i64,
+    pub total_nodes: i64,
+    pub total_pages: i64,
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+}
+
+#[derive(SimpleObject, Debug)]
+#[graphql(name_type)]
+pub struct OffsetPage<T: OutputType> {
+    pub items: Vec<T>,
+    pub page_info: OffsetPageInfo,
+}
+
+impl<T: OutputType> TypeName for OffsetPage<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Page", T::type_name()).into()
+    }
+}
+
+impl<T> From<OffsetPagination<T>> for OffsetPage<T>
+where
+    T: OutputType + Model + Debug,
+{
+    fn from(value: OffsetPagination<T>) -> Self {
+        let page_info = OffsetPageInfo {
+            page: value.page,
+            per_page: value.per_page,
+            total_nodes: value.total_nodes,
+            total_pages: value.total_pages,
+            has_next_page: value.has_next(),
+            has_previous_page: value.has_previous(),
+        };
+
+        Self {
+            items: value.items,
+            page_info,
+        }
+    }
+}
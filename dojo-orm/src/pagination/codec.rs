@@ -0,0 +1,269 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::pagination::{Cursor, Row};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const VERSION: u8 = 1;
+const FLAG_SIGNED: u8 = 0b01;
+const FLAG_ENCRYPTED: u8 = 0b10;
+const TAG_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CursorError {
+    InvalidEncoding,
+    UnsupportedVersion(u8),
+    MissingSignature,
+    InvalidSignature,
+    MissingKey,
+    InvalidPayload,
+    MismatchedColumns {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+}
+
+impl Display for CursorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CursorError::InvalidEncoding => write!(f, "cursor is not valid base64"),
+            CursorError::UnsupportedVersion(version) => {
+                write!(f, "unsupported cursor version {}", version)
+            }
+            CursorError::MissingSignature => write!(f, "cursor is not signed"),
+            CursorError::InvalidSignature => write!(f, "cursor signature is invalid"),
+            CursorError::MissingKey => write!(f, "no key configured to open the cursor"),
+            CursorError::InvalidPayload => write!(f, "cursor payload is invalid"),
+            CursorError::MismatchedColumns { expected, actual } => write!(
+                f,
+                "cursor columns {:?} don't match sort keys {:?}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for CursorError {}
+
+/// Encodes cursors as `version | flags | payload | tag`, in url safe base64.
+///
+/// With a signing key every cursor carries an HMAC-SHA256 tag and unsigned
+/// or altered cursors are rejected. With an encryption key the payload is
+/// sealed with AES-256-GCM so clients can't read the values either.
+#[derive(Clone, Default)]
+pub struct CursorCodec {
+    signing_key: Option<Vec<u8>>,
+    encryption_key: Option<Key<Aes256Gcm>>,
+}
+
+impl Debug for CursorCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorCodec")
+            .field("signed", &self.signing_key.is_some())
+            .field("encrypted", &self.encryption_key.is_some())
+            .finish()
+    }
+}
+
+impl CursorCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_signing_key(mut self, key: impl AsRef<[u8]>) -> Self {
+        self.signing_key = Some(key.as_ref().to_vec());
+        self
+    }
+
+    pub fn with_encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(key.into());
+        self
+    }
+
+    pub fn encode(&self, cursor: &Cursor) -> anyhow::Result<String> {
+        let mut flags = 0;
+        let mut payload = bincode::serialize(&cursor.values)?;
+
+        if let Some(key) = &self.encryption_key {
+            flags |= FLAG_ENCRYPTED;
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = Aes256Gcm::new(key)
+                .encrypt(&nonce, payload.as_slice())
+                .map_err(|_| anyhow::anyhow!("failed to encrypt cursor"))?;
+            payload = [nonce.as_slice(), ciphertext.as_slice()].concat();
+        }
+
+        if self.signing_key.is_some() {
+            flags |= FLAG_SIGNED;
+        }
+
+        let mut buf = vec![VERSION, flags];
+        buf.extend(payload);
+
+        if let Some(key) = &self.signing_key {
+            let tag = Self::mac(key, &buf).finalize().into_bytes();
+            buf.extend(tag);
+        }
+
+        Ok(Base64UrlUnpadded::encode_string(&buf))
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<Cursor, CursorError> {
+        let buf =
+            Base64UrlUnpadded::decode_vec(encoded).map_err(|_| CursorError::InvalidEncoding)?;
+        let (version, flags) = match buf.as_slice() {
+            [version, flags, ..] => (*version, *flags),
+            _ => return Err(CursorError::InvalidPayload),
+        };
+        if version != VERSION {
+            return Err(CursorError::UnsupportedVersion(version));
+        }
+
+        let is_signed = flags & FLAG_SIGNED != 0;
+        let mut body = &buf[..];
+        match (&self.signing_key, is_signed) {
+            (Some(key), true) => {
+                if body.len() < 2 + TAG_LEN {
+                    return Err(CursorError::InvalidPayload);
+                }
+                let (signed, tag) = body.split_at(body.len() - TAG_LEN);
+                Self::mac(key, signed)
+                    .verify_slice(tag)
+                    .map_err(|_| CursorError::InvalidSignature)?;
+                body = signed;
+            }
+            (Some(_), false) => return Err(CursorError::MissingSignature),
+            (None, true) => return Err(CursorError::MissingKey),
+            (None, false) => {}
+        }
+
+        let payload = &body[2..];
+        let is_encrypted = flags & FLAG_ENCRYPTED != 0;
+        let payload = if is_encrypted {
+            let key = self
+                .encryption_key
+                .as_ref()
+                .ok_or(CursorError::MissingKey)?;
+            if payload.len() < NONCE_LEN {
+                return Err(CursorError::InvalidPayload);
+            }
+            let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+            Aes256Gcm::new(key)
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| CursorError::InvalidPayload)?
+        } else {
+            payload.to_vec()
+        };
+
+        let values: Vec<Row> =
+            bincode::deserialize(&payload).map_err(|_| CursorError::InvalidPayload)?;
+
+        let mut cursor = Cursor::new(values);
+        cursor.unverified = !is_signed && !is_encrypted;
+
+        Ok(cursor)
+    }
+
+    pub(crate) fn is_keyed(&self) -> bool {
+        self.signing_key.is_some() || self.encryption_key.is_some()
+    }
+
+    fn mac(key: &[u8], buf: &[u8]) -> HmacSha256 {
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(key).expect("hmac accepts keys of any size");
+        mac.update(buf);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+    use uuid::Uuid;
+
+    fn cursor() -> Cursor {
+        Cursor::new(vec![
+            Row::new("name".to_string(), Value::String("linh".to_string())),
+            Row::new(
+                "id".to_string(),
+                Value::Uuid(Uuid::parse_str("ce2087a7-bdbc-4453-9fb8-d4dff3584f3e").unwrap()),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_encode_decode_unsigned() -> anyhow::Result<()> {
+        let codec = CursorCodec::new();
+        let encoded = codec.encode(&cursor())?;
+        let decoded = codec.decode(&encoded)?;
+        assert_eq!(decoded.values.len(), 2);
+        assert_eq!(decoded.values[0].column, "name");
+        assert!(decoded.unverified);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_decode_signed_and_encrypted() -> anyhow::Result<()> {
+        let codec = CursorCodec::new()
+            .with_signing_key("secret")
+            .with_encryption_key([7; 32]);
+        let encoded = codec.encode(&cursor())?;
+        let decoded = codec.decode(&encoded)?;
+        assert_eq!(decoded.values[1].value, cursor().values[1].value);
+        assert!(!decoded.unverified);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_tampered() -> anyhow::Result<()> {
+        let codec = CursorCodec::new().with_signing_key("secret");
+        let encoded = codec.encode(&cursor())?;
+
+        let mut buf = Base64UrlUnpadded::decode_vec(&encoded).unwrap();
+        buf[4] ^= 1;
+        let tampered = Base64UrlUnpadded::encode_string(&buf);
+        assert_eq!(
+            codec.decode(&tampered).unwrap_err(),
+            CursorError::InvalidSignature
+        );
+
+        let other = CursorCodec::new().with_signing_key("other");
+        assert_eq!(
+            other.decode(&encoded).unwrap_err(),
+            CursorError::InvalidSignature
+        );
+
+        let unsigned = CursorCodec::new().encode(&cursor())?;
+        assert_eq!(
+            codec.decode(&unsigned).unwrap_err(),
+            CursorError::MissingSignature
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_garbage() {
+        let codec = CursorCodec::new();
+        assert_eq!(
+            codec.decode("not base64!").unwrap_err(),
+            CursorError::InvalidEncoding
+        );
+        assert_eq!(codec.decode("AA").unwrap_err(), CursorError::InvalidPayload);
+        assert_eq!(
+            codec.decode("CQA").unwrap_err(),
+            CursorError::UnsupportedVersion(9)
+        );
+    }
+}
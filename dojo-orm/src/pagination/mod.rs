@@ -1,14 +1,14 @@
 mod async_graphql;
+mod codec;
 
 use std::error::Error;
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
-use crate::order_by::Direction;
+use crate::order_by::{Direction, SortKey};
 use crate::types::{accepts, to_sql_checked, IsNull, ToSql, Type};
-use crate::Model;
+use crate::{Model, Value};
 use anyhow::Result;
-use base64ct::{Base64, Encoding};
 use bytes::BytesMut;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
@@ -16,6 +16,7 @@ use tracing::debug;
 use tracing_subscriber::fmt::format;
 
 pub use async_graphql::*;
+pub use codec::*;
 
 pub trait DefaultSortKeys {
     fn keys() -> Vec<String>;
@@ -42,6 +43,10 @@ pub trait DefaultSortKeys {
     }
 }
 
+pub(crate) fn is_backward(first: Option<i64>, last: Option<i64>, has_before: bool) -> bool {
+    last.is_some() || (first.is_none() && has_before)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Row {
     pub column: String,
@@ -57,62 +62,174 @@ impl Row {
 #[derive(Debug, Clone)]
 pub struct Cursor {
     pub values: Vec<Row>,
+    // decoded without a signature or encryption to check, a database with
+    // a keyed codec refuses to page from it
+    pub(crate) unverified: bool,
 }
 
 impl Cursor {
     pub fn to_where_stmt(
         &self,
-        direction: Direction,
+        keys: &[SortKey],
         params_index: &mut usize,
-    ) -> (String, Vec<&(dyn ToSql + Sync)>) {
-        let mut columns = vec![];
-        let mut stmt = "".to_string();
-
-        for value in &self.values {
-            columns.push(value.column.clone());
+    ) -> Result<(String, Vec<&(dyn ToSql + Sync)>)> {
+        let columns = self
+            .values
+            .iter()
+            .map(|value| value.column.as_str())
+            .collect::<Vec<_>>();
+        let key_columns = keys.iter().map(|key| key.column).collect::<Vec<_>>();
+        if columns != key_columns {
+            return Err(CursorError::MismatchedColumns {
+                expected: key_columns.iter().map(|c| c.to_string()).collect(),
+                actual: columns.iter().map(|c| c.to_string()).collect(),
+            }
+            .into());
         }
 
-        stmt.push('(');
-        stmt.push_str(&columns.join(", "));
-        stmt.push_str(") ");
-        let ch = if direction == Direction::Asc {
-            '>'
-        } else {
-            '<'
-        };
-        stmt.push(ch);
-        stmt.push_str(" (");
-
+        // each non null value is bound once and reused by every comparison
         let mut params: Vec<&(dyn ToSql + Sync)> = vec![];
         let mut args = vec![];
         for value in &self.values {
-            params.push(&value.value);
-            args.push(format!("${}", params_index));
-            *params_index += 1;
+            if value.value == Value::Null {
+                args.push(None);
+            } else {
+                params.push(&value.value);
+                args.push(Some(format!("${}", params_index)));
+                *params_index += 1;
+            }
         }
 
-        stmt.push_str(&args.join(", "));
-        stmt.push(')');
+        let direction = keys.first().map(|key| key.direction);
+        let is_row_comparable = keys.iter().all(|key| Some(key.direction) == direction)
+            && keys.iter().all(|key| !key.nullable)
+            && args.iter().all(|arg| arg.is_some());
+
+        if is_row_comparable {
+            let ch = if direction == Some(Direction::Desc) {
+                '<'
+            } else {
+                '>'
+            };
+            let args = args.into_iter().flatten().collect::<Vec<_>>();
+            let stmt = format!("({}) {} ({})", key_columns.join(", "), ch, args.join(", "));
+
+            return Ok((stmt, params));
+        }
 
-        (stmt, params)
+        // a > $1 OR (a = $1 AND b < $2) OR ...
+        let mut terms = vec![];
+        for (i, key) in keys.iter().enumerate() {
+            let Some(after) = Self::after_stmt(key, &args[i]) else {
+                continue;
+            };
+
+            let mut conditions = keys[..i]
+                .iter()
+                .zip(&args)
+                .map(|(key, arg)| match arg {
+                    Some(arg) => format!("{} = {}", key.column, arg),
+                    None => format!("{} IS NULL", key.column),
+                })
+                .collect::<Vec<_>>();
+            conditions.push(after);
+
+            if conditions.len() == 1 {
+                terms.push(conditions.remove(0));
+            } else {
+                terms.push(format!("({})", conditions.join(" AND ")));
+            }
+        }
+
+        let stmt = if terms.is_empty() {
+            "FALSE".to_string()
+        } else {
+            format!("({})", terms.join(" OR "))
+        };
+
+        Ok((stmt, params))
+    }
+
+    // Rows strictly after the cursor value on a single key. Postgres sorts
+    // NULLs last on ASC and first on DESC.
+    fn after_stmt(key: &SortKey, arg: &Option<String>) -> Option<String> {
+        match (key.direction, arg) {
+            (Direction::Desc, Some(arg)) => Some(format!("{} < {}", key.column, arg)),
+            (Direction::Desc, None) => Some(format!("{} IS NOT NULL", key.column)),
+            (_, Some(arg)) if key.nullable => Some(format!(
+                "({} > {} OR {} IS NULL)",
+                key.column, arg, key.column
+            )),
+            (_, Some(arg)) => Some(format!("{} > {}", key.column, arg)),
+            (_, None) => None,
+        }
     }
 }
 
 impl Cursor {
     pub fn new(values: Vec<Row>) -> Self {
-        Self { values }
+        Self {
+            values,
+            unverified: false,
+        }
     }
 
-    pub fn encode(&self) -> String {
-        // it's safe, trust me bro.
-        let buf = bincode::serialize(&self.values).unwrap();
-        Base64::encode_string(&buf)
+    /// Encodes without signing or encryption, use [`crate::Database::encode_cursor`]
+    /// for a database configured with a [`CursorCodec`].
+    pub fn encode(&self) -> Result<String> {
+        CursorCodec::new().encode(self)
     }
 
-    pub fn decode(encoded: &str) -> Result<Self> {
-        let decoded = Base64::decode_vec(encoded).unwrap();
-        let values: Vec<Row> = bincode::deserialize(&decoded[..])?;
-        Ok(Self { values })
+    /// Decodes an unsigned, unencrypted cursor, use
+    /// [`crate::Database::decode_cursor`] for a database configured with a
+    /// [`CursorCodec`].
+    pub fn decode(encoded: &str) -> Result<Self, CursorError> {
+        CursorCodec::new().decode(encoded)
+    }
+}
+
+/// How cursor pagination fills in `total_nodes`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum TotalCount {
+    /// Run a `COUNT(*)` over the filtered rows alongside the page query.
+    #[default]
+    Exact,
+    /// Skip counting, `total_nodes` is `None`.
+    None,
+    /// Use the planner estimate: `pg_class.reltuples` when there is no
+    /// filter, the `EXPLAIN` row estimate otherwise.
+    Estimated,
+}
+
+/// Rows of a cursor page when neither `first` nor `last` is given.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+#[derive(Debug, Copy, Clone)]
+pub struct CursorOptions {
+    pub total_count: TotalCount,
+    /// Rows of a page when neither `first` nor `last` is given, guarded by
+    /// [`crate::MaxRows`] like they are.
+    pub page_size: i64,
+}
+
+impl Default for CursorOptions {
+    fn default() -> Self {
+        Self {
+            total_count: TotalCount::default(),
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl CursorOptions {
+    pub fn total_count(mut self, total_count: TotalCount) -> Self {
+        self.total_count = total_count;
+        self
+    }
+
+    pub fn page_size(mut self, page_size: i64) -> Self {
+        self.page_size = page_size;
+        self
     }
 }
 
@@ -126,9 +243,11 @@ where
     pub after: Option<Cursor>,
     pub first: Option<i64>,
     pub last: Option<i64>,
-    pub total_nodes: i64,
+    pub total_nodes: Option<i64>,
     pub has_next: bool,
     pub has_previous: bool,
+    pub sort_keys: Vec<String>,
+    codec: CursorCodec,
 }
 
 impl<T> Pagination<T>
@@ -141,18 +260,31 @@ where
         after: Option<Cursor>,
         last: Option<i64>,
         before: Option<Cursor>,
-        total_nodes: i64,
+        total_nodes: Option<i64>,
+        sort_keys: Vec<String>,
     ) -> Self {
-        let limit = first.or(last).unwrap_or(0);
-        let has_next_or_previous = items.len() as i64 == limit + 1;
-        let has_next = first.is_some() && has_next_or_previous;
-        let has_previous = last.is_some() && has_next_or_previous;
-
+        // the query fetches one extra row to know whether there is more
         let mut items = items;
-        if has_next_or_previous {
-            items.pop();
+        let has_more = match first.or(last) {
+            Some(limit) if items.len() as i64 > limit => {
+                items.truncate(limit as usize);
+                true
+            }
+            _ => false,
+        };
+
+        // backward pages are scanned in reverse, flip them back
+        let backward = is_backward(first, last, before.is_some());
+        if backward {
+            items.reverse();
         }
 
+        let (has_next, has_previous) = if backward {
+            (before.is_some(), has_more)
+        } else {
+            (has_more, after.is_some())
+        };
+
         Self {
             items,
             before,
@@ -162,11 +294,72 @@ where
             total_nodes,
             has_next,
             has_previous,
+            sort_keys,
+            codec: CursorCodec::default(),
         }
     }
 
+    pub(crate) fn with_codec(mut self, codec: CursorCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    pub fn start_cursor(&self) -> Option<Cursor> {
+        self.items
+            .first()
+            .map(|item| item.cursor_by(&self.sort_keys))
+    }
+
     pub fn end_cursor(&self) -> Option<Cursor> {
-        self.items.last().map(|item| item.cursor())
+        self.items
+            .last()
+            .map(|item| item.cursor_by(&self.sort_keys))
+    }
+
+    /// Encodes `cursor` with the codec of the database the page came from.
+    pub fn encode_cursor(&self, cursor: &Cursor) -> Result<String> {
+        self.codec.encode(cursor)
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetPagination<T>
+where
+    T: Model + Debug,
+{
+    pub items: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total_nodes: i64,
+    pub total_pages: i64,
+}
+
+impl<T> OffsetPagination<T>
+where
+    T: Model + Debug,
+{
+    pub fn new(items: Vec<T>, page: i64, per_page: i64, total_nodes: i64) -> Self {
+        let total_pages = if per_page > 0 {
+            total_nodes / per_page + i64::from(total_nodes % per_page != 0)
+        } else {
+            0
+        };
+
+        Self {
+            items,
+            page,
+            per_page,
+            total_nodes,
+            total_pages,
+        }
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
     }
 }
 
@@ -186,9 +379,10 @@ mod tests {
             value: crate::model::Value::NaiveDateTime(created_at),
         };
         let cursor = Cursor::new(vec![cursor_value]);
-        let (sql, params) = cursor.to_where_stmt(Direction::Asc, &mut 1);
-        println!("sql: {}", sql);
-        println!("params: {:?}", params);
+        let keys = [SortKey::new("created_at", Direction::Asc)];
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 1)?;
+        assert_eq!(sql, "(created_at) > ($1)");
+        assert_eq!(params.len(), 1);
 
         Ok(())
     }
@@ -207,9 +401,90 @@ mod tests {
                 value: crate::model::Value::Uuid(uuid),
             },
         ]);
-        let (sql, params) = cursor.to_where_stmt(Direction::Asc, &mut 1);
-        println!("sql: {}", sql);
-        println!("params: {:?}", params);
+        let keys = [
+            SortKey::new("created_at", Direction::Desc),
+            SortKey::new("id", Direction::Desc),
+        ];
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 3)?;
+        assert_eq!(sql, "(created_at, id) < ($3, $4)");
+        assert_eq!(params.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_to_sql_with_mixed_directions() -> anyhow::Result<()> {
+        let uuid = Uuid::parse_str("ce2087a7-bdbc-4453-9fb8-d4dff3584f3e")?;
+        let cursor = Cursor::new(vec![
+            Row::new("name".to_string(), Value::String("linh".to_string())),
+            Row::new("age".to_string(), Value::Int32(20)),
+            Row::new("id".to_string(), Value::Uuid(uuid)),
+        ]);
+        let keys = [
+            SortKey::new("name", Direction::Asc),
+            SortKey::new("age", Direction::Desc),
+            SortKey::new("id", Direction::Asc),
+        ];
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 1)?;
+        assert_eq!(
+            sql,
+            "(name > $1 OR (name = $1 AND age < $2) OR (name = $1 AND age = $2 AND id > $3))"
+        );
+        assert_eq!(params.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_to_sql_with_nullable_key() -> anyhow::Result<()> {
+        let uuid = Uuid::parse_str("ce2087a7-bdbc-4453-9fb8-d4dff3584f3e")?;
+        let mut price = SortKey::new("price", Direction::Asc);
+        price.nullable = true;
+        let keys = [price.clone(), SortKey::new("id", Direction::Asc)];
+
+        let cursor = Cursor::new(vec![
+            Row::new("price".to_string(), Value::Int32(100)),
+            Row::new("id".to_string(), Value::Uuid(uuid)),
+        ]);
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 1)?;
+        assert_eq!(
+            sql,
+            "((price > $1 OR price IS NULL) OR (price = $1 AND id > $2))"
+        );
+        assert_eq!(params.len(), 2);
+
+        let cursor = Cursor::new(vec![
+            Row::new("price".to_string(), Value::Null),
+            Row::new("id".to_string(), Value::Uuid(uuid)),
+        ]);
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 1)?;
+        assert_eq!(sql, "((price IS NULL AND id > $1))");
+        assert_eq!(params.len(), 1);
+
+        price.direction = Direction::Desc;
+        let keys = [price, SortKey::new("id", Direction::Desc)];
+        let (sql, params) = cursor.to_where_stmt(&keys, &mut 1)?;
+        assert_eq!(sql, "(price IS NOT NULL OR (price IS NULL AND id < $1))");
+        assert_eq!(params.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_to_sql_with_mismatched_keys() -> anyhow::Result<()> {
+        let cursor = Cursor::new(vec![Row::new(
+            "password".to_string(),
+            Value::String("x".to_string()),
+        )]);
+        let keys = [SortKey::new("id", Direction::Asc)];
+        let err = cursor.to_where_stmt(&keys, &mut 1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CursorError>(),
+            Some(&CursorError::MismatchedColumns {
+                expected: vec!["id".to_string()],
+                actual: vec!["password".to_string()],
+            })
+        );
 
         Ok(())
     }
@@ -222,7 +497,7 @@ mod tests {
             value: crate::model::Value::NaiveDateTime(created_at),
         };
         let cursor = Cursor::new(vec![cursor_value]);
-        let encoded = cursor.encode();
+        let encoded = cursor.encode()?;
 
         let decoded = Cursor::decode(&encoded).unwrap();
         assert_that!(
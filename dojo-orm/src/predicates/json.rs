@@ -0,0 +1,153 @@
+use crate::column::{AcceptsElement, AcceptsValue, ColumnRef, ListValue};
+use crate::predicates::{Expr, ExprValue, WherePredicate};
+use crate::raw::RawSql;
+use crate::types::ToSql;
+
+/// A value extracted as text from a jsonb column.
+///
+/// It can be used anywhere a column is, e.g. `equals(&color, &"red")` or
+/// `asc(&color)`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JsonPath {
+    expr: String,
+}
+
+impl JsonPath {
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+}
+
+impl<'a> ColumnRef<'a> for &'a JsonPath {
+    type Predicate = WherePredicate<'a>;
+
+    fn column_name(&self) -> &'a str {
+        self.expr.as_str()
+    }
+}
+
+impl<V: ?Sized> AcceptsValue<V> for &JsonPath {}
+
+impl<V: ?Sized> AcceptsElement<V> for &JsonPath {}
+
+/// `column->>'key'`
+pub fn json_field<'a>(column: impl ColumnRef<'a>, key: &str) -> JsonPath {
+    JsonPath {
+        expr: format!("{}->>{}", column.column_name(), quote_literal(key)),
+    }
+}
+
+/// `column#>>'{a,b}'`
+pub fn json_path<'a>(column: impl ColumnRef<'a>, path: &[&str]) -> JsonPath {
+    JsonPath {
+        expr: format!("{}#>>{}", column.column_name(), path_literal(path)),
+    }
+}
+
+/// A `text[]` literal such as `'{"a","b"}'` naming a path in a document.
+pub(crate) fn path_literal(path: &[&str]) -> String {
+    let path = path
+        .iter()
+        .map(|key| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    quote_literal(&format!("{{{}}}", path))
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// `column @> $1`, where the value is anything bound as jsonb: an
+/// `EmbeddedModel`, a `serde_json::Value` or a `types::Json`.
+pub fn json_contains<'a, C, V>(column: C, value: &'a V) -> C::Predicate
+where
+    C: ColumnRef<'a>,
+    V: ToSql + Sync,
+{
+    json_op(column.column_name(), "@>", value).into()
+}
+
+/// `column ? 'key'`
+pub fn json_has_key<'a, C: ColumnRef<'a>>(column: C, key: &str) -> C::Predicate {
+    WherePredicate::Value(Expr::Raw(RawSql::new(format!(
+        "{} ? {}",
+        column.column_name(),
+        quote_literal(key)
+    ))))
+    .into()
+}
+
+/// `column ?| $1`
+pub fn json_has_any_key<'a, C, L>(column: C, keys: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a>,
+    L: ListValue + ToSql + Sync,
+{
+    json_op(column.column_name(), "?|", keys).into()
+}
+
+/// `column ?& $1`
+pub fn json_has_all_keys<'a, C, L>(column: C, keys: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a>,
+    L: ListValue + ToSql + Sync,
+{
+    json_op(column.column_name(), "?&", keys).into()
+}
+
+/// `jsonb_path_exists(column, 'path')`, with `path` a SQL/JSON path such as
+/// `$.tags[*] ? (@ == "new")`.
+pub fn json_path_exists<'a, C: ColumnRef<'a>>(column: C, path: &str) -> C::Predicate {
+    WherePredicate::Value(Expr::Raw(RawSql::new(format!(
+        "jsonb_path_exists({}, {}::jsonpath)",
+        column.column_name(),
+        quote_literal(path)
+    ))))
+    .into()
+}
+
+fn json_op<'a>(
+    column: &'a str,
+    condition: &'a str,
+    value: &'a (dyn ToSql + Sync),
+) -> WherePredicate<'a> {
+    WherePredicate::Value(Expr::Value(ExprValue {
+        column: column.into(),
+        condition,
+        value,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::order_by::asc;
+    use crate::predicates::*;
+
+    #[test]
+    fn test_json() {
+        let color = json_field("detail", "color");
+        let size = json_path("detail", &["size", "it's"]);
+        let value = serde_json::json!({ "color": "red" });
+        let keys = vec!["color", "size"];
+        let predicates = [
+            equals(&color, &"red"),
+            equals(&size, &"xl"),
+            json_contains("detail", &value),
+            json_has_key("detail", "it's"),
+            json_has_any_key("detail", &keys),
+            json_has_all_keys("detail", &keys),
+            json_path_exists("detail", "$.size"),
+        ];
+        let predicates = and(&predicates);
+
+        let (query, params) = predicates.to_sql(&mut 1);
+        assert_eq!(
+            query.unwrap(),
+            "(detail->>'color' = $1 AND detail#>>'{\"size\",\"it''s\"}' = $2 AND detail @> $3 AND detail ? 'it''s' AND detail ?| $4 AND detail ?& $5 AND jsonb_path_exists(detail, '$.size'::jsonpath))"
+        );
+        assert_eq!(params.len(), 5);
+        assert_eq!(asc(&color).column(), "detail->>'color'");
+    }
+}
@@ -1,7 +1,17 @@
 use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
 
+use crate::column::{AcceptsElement, AcceptsValue, ColumnRef, ListValue};
+use crate::raw::RawSql;
 use crate::types::ToSql;
 
+pub use json::*;
+pub use subquery::*;
+
+mod json;
+mod subquery;
+
 #[derive(Debug, Copy, Clone)]
 pub enum ExprValueType {
     Value,
@@ -31,29 +41,47 @@ pub struct ExprFunction<'a> {
     pub args: &'a [&'a (dyn ToSql + Sync)],
 }
 
+/// How a computed value is compared with a parameter.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::Eq => write!(f, "="),
+            Comparison::Ne => write!(f, "<>"),
+            Comparison::Lt => write!(f, "<"),
+            Comparison::Lte => write!(f, "<="),
+            Comparison::Gt => write!(f, ">"),
+            Comparison::Gte => write!(f, ">="),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Expr<'a> {
     Value(ExprValue<'a>),
     Array(ExprArray<'a>),
     Function(ExprFunction<'a>),
-    Raw(String),
-    RawStr(&'a str),
+    Raw(RawSql<'a>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum WherePredicate<'a> {
     Value(Expr<'a>),
     And(&'a [WherePredicate<'a>]),
     Or(&'a [WherePredicate<'a>]),
+    #[default]
     Empty,
 }
 
-impl<'a> Default for WherePredicate<'a> {
-    fn default() -> Self {
-        WherePredicate::Empty
-    }
-}
-
 impl<'a> WherePredicate<'a> {
     pub fn to_sql(
         &self,
@@ -97,8 +125,12 @@ impl<'a> WherePredicate<'a> {
 
                         query
                     }
-                    Expr::Raw(raw) => raw.to_string(),
-                    Expr::RawStr(raw) => raw.to_string(),
+                    Expr::Raw(raw) => {
+                        let (query, raw_params) = raw.to_sql(params_index);
+                        params.extend(raw_params);
+
+                        query
+                    }
                 };
 
                 (Some(query), params)
@@ -144,6 +176,65 @@ impl<'a> WherePredicate<'a> {
     }
 }
 
+/// A predicate on a typed [`Column`](crate::Column) of model `M`, which
+/// `where_by` only accepts on operations over `M`. Convert it into a
+/// [`WherePredicate`] to combine it with others in [`and`] or [`or`].
+pub struct ModelPredicate<'a, M> {
+    predicate: WherePredicate<'a>,
+    _m: PhantomData<fn() -> M>,
+}
+
+impl<M> Debug for ModelPredicate<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ModelPredicate")
+            .field(&self.predicate)
+            .finish()
+    }
+}
+
+impl<M> Clone for ModelPredicate<'_, M> {
+    fn clone(&self) -> Self {
+        self.predicate.clone().into()
+    }
+}
+
+impl<'a, M> From<WherePredicate<'a>> for ModelPredicate<'a, M> {
+    fn from(predicate: WherePredicate<'a>) -> Self {
+        Self {
+            predicate,
+            _m: PhantomData,
+        }
+    }
+}
+
+impl<'a, M> From<ModelPredicate<'a, M>> for WherePredicate<'a> {
+    fn from(predicate: ModelPredicate<'a, M>) -> Self {
+        predicate.predicate
+    }
+}
+
+/// What `where_by` accepts on an operation over model `M`: any
+/// [`WherePredicate`], or a [`ModelPredicate`] of `M`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a predicate on `{M}`",
+    label = "built on a column of another model"
+)]
+pub trait IntoWherePredicate<'a, M> {
+    fn into_where_predicate(self) -> WherePredicate<'a>;
+}
+
+impl<'a, 'b: 'a, M> IntoWherePredicate<'a, M> for WherePredicate<'b> {
+    fn into_where_predicate(self) -> WherePredicate<'a> {
+        self
+    }
+}
+
+impl<'a, 'b: 'a, M> IntoWherePredicate<'a, M> for ModelPredicate<'b, M> {
+    fn into_where_predicate(self) -> WherePredicate<'a> {
+        self.predicate
+    }
+}
+
 pub fn and<'a>(predicates: &'a [WherePredicate<'a>]) -> WherePredicate<'a> {
     WherePredicate::And(predicates)
 }
@@ -152,38 +243,115 @@ pub fn or<'a>(predicates: &'a [WherePredicate<'a>]) -> WherePredicate<'a> {
     WherePredicate::Or(predicates)
 }
 
-pub fn equals<'a, T: ToSql + Sync>(column: &'a str, value: &'a T) -> WherePredicate<'a> {
+pub fn equals<'a, C, V>(column: C, value: &'a V) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsValue<V>,
+    V: ToSql + Sync,
+{
     WherePredicate::Value(Expr::Value(ExprValue {
-        column: column.into(),
+        column: column.column_name().into(),
         condition: "=",
         value,
     }))
+    .into()
 }
 
-pub fn in_list<'a>(column: &'a str, values: &'a (dyn ToSql + Sync)) -> WherePredicate<'a> {
+pub fn in_list<'a, C, L>(column: C, values: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsValue<L::Item>,
+    L: ListValue + ToSql + Sync,
+{
     WherePredicate::Value(Expr::Array(ExprArray {
-        column: column.into(),
+        column: column.column_name().into(),
         condition: "=",
         values,
     }))
+    .into()
 }
 
-pub fn text_search<'a>(column: &'a str, lang: &'a str, value: &'a str) -> WherePredicate<'a> {
-    WherePredicate::Value(Expr::Raw(
-        format!(
-            "{} @@ websearch_to_tsquery('{}', '{}')",
-            column, lang, value
-        )
-        .to_string(),
-    ))
+pub fn array_contains<'a, C, L>(column: C, values: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsElement<L::Item>,
+    L: ListValue + ToSql + Sync,
+{
+    array_op(column.column_name(), "@>", values).into()
 }
 
-pub fn raw<'a>(raw: String) -> WherePredicate<'a> {
-    WherePredicate::Value(Expr::Raw(raw))
+pub fn array_overlaps<'a, C, L>(column: C, values: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsElement<L::Item>,
+    L: ListValue + ToSql + Sync,
+{
+    array_op(column.column_name(), "&&", values).into()
 }
 
-pub fn raw_str(raw: &str) -> WherePredicate {
-    WherePredicate::Value(Expr::RawStr(raw))
+pub fn array_contained_by<'a, C, L>(column: C, values: &'a L) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsElement<L::Item>,
+    L: ListValue + ToSql + Sync,
+{
+    array_op(column.column_name(), "<@", values).into()
+}
+
+fn array_op<'a>(
+    column: &'a str,
+    condition: &'a str,
+    values: &'a (dyn ToSql + Sync),
+) -> WherePredicate<'a> {
+    WherePredicate::Value(Expr::Value(ExprValue {
+        column: column.into(),
+        condition,
+        value: values,
+    }))
+}
+
+/// Matches rows whose array `column` holds `op` `len` elements, counting
+/// every dimension. Unlike `array_length`, an empty array counts 0.
+pub fn array_cardinality<'a, C: ColumnRef<'a>>(
+    column: C,
+    op: Comparison,
+    len: &'a i32,
+) -> C::Predicate {
+    WherePredicate::Value(Expr::Raw(RawSql::with_params(
+        format!("cardinality({}) {} $1", column.column_name(), op),
+        vec![len],
+    )))
+    .into()
+}
+
+/// `value = ANY(column)`: matches rows whose array `column` holds `value`.
+pub fn array_any<'a, C, V>(column: C, value: &'a V) -> C::Predicate
+where
+    C: ColumnRef<'a> + AcceptsElement<V>,
+    V: ToSql + Sync,
+{
+    WherePredicate::Value(Expr::Raw(RawSql::with_params(
+        format!("$1 = ANY({})", column.column_name()),
+        vec![value],
+    )))
+    .into()
+}
+
+/// Matches `column` against a web search style query, e.g. `"rust -java"`,
+/// parsed with the text search configuration `lang`. Both are bound.
+pub fn text_search<'a, C, L, V>(column: C, lang: &'a L, value: &'a V) -> C::Predicate
+where
+    C: ColumnRef<'a>,
+    L: ToSql + Sync,
+    V: ToSql + Sync,
+{
+    WherePredicate::Value(Expr::Raw(RawSql::with_params(
+        format!(
+            "{} @@ websearch_to_tsquery($1::text::regconfig, $2)",
+            column.column_name()
+        ),
+        vec![lang, value],
+    )))
+    .into()
+}
+
+pub fn raw<'a>(raw: impl Into<RawSql<'a>>) -> WherePredicate<'a> {
+    WherePredicate::Value(Expr::Raw(raw.into()))
 }
 
 #[cfg(test)]
@@ -210,15 +378,55 @@ mod tests {
 
     #[test]
     fn test_text_search() {
-        let predicates = [text_search("name", "english", "test")];
+        let predicates = [
+            equals("id", &1),
+            text_search("name", &"english", &"it's a test"),
+        ];
+        let predicates = and(&predicates);
+
+        let (query, params) = predicates.to_sql(&mut 1);
+        assert_eq!(
+            query.unwrap(),
+            "(id = $1 AND name @@ websearch_to_tsquery($2::text::regconfig, $3))"
+        );
+        assert_eq!(params.len(), 3);
+    }
+
+    #[test]
+    fn test_raw() {
+        let predicates = [
+            equals("id", &1),
+            raw(crate::sql!("age > $1 AND age < $2", &18, &30)),
+            equals("name", &"test"),
+        ];
+        let predicates = and(&predicates);
+
+        let (query, params) = predicates.to_sql(&mut 1);
+        assert_eq!(
+            query.unwrap(),
+            "(id = $1 AND age > $2 AND age < $3 AND name = $4)"
+        );
+        assert_eq!(params.len(), 4);
+    }
+
+    #[test]
+    fn test_array() {
+        let tags = vec!["rust", "sql"];
+        let predicates = [
+            array_contains("tags", &tags),
+            array_overlaps("tags", &tags),
+            array_contained_by("tags", &tags),
+            array_cardinality("tags", Comparison::Gte, &2),
+            array_any("tags", &"rust"),
+        ];
         let predicates = and(&predicates);
 
         let (query, params) = predicates.to_sql(&mut 1);
         assert_eq!(
             query.unwrap(),
-            "(name @@ websearch_to_tsquery('english', 'test'))"
+            "(tags @> $1 AND tags && $2 AND tags <@ $3 AND cardinality(tags) >= $4 AND $5 = ANY(tags))"
         );
-        assert_eq!(params.len(), 0);
+        assert_eq!(params.len(), 5);
     }
 
     #[test]
@@ -0,0 +1,126 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+
+use crate::column::ColumnRef;
+use crate::model::Model;
+use crate::operations::SelectOperation;
+use crate::predicates::{Expr, WherePredicate};
+use crate::raw::RawSql;
+
+/// Matches when `select` returns at least one row. Columns of the outer
+/// table can be referenced through its name, e.g.
+/// `raw("orders.user_id = users.id")`. Fails if `select` doesn't build.
+pub fn exists<'a, T>(select: &'a SelectOperation<'a, T>) -> Result<WherePredicate<'a>>
+where
+    T: Model + Debug,
+{
+    Ok(subquery_predicate(
+        "EXISTS (",
+        select.subquery(&["1"])?,
+        ")",
+    ))
+}
+
+pub fn not_exists<'a, T>(select: &'a SelectOperation<'a, T>) -> Result<WherePredicate<'a>>
+where
+    T: Model + Debug,
+{
+    Ok(subquery_predicate(
+        "NOT EXISTS (",
+        select.subquery(&["1"])?,
+        ")",
+    ))
+}
+
+/// `column IN (subquery)`, where the subquery selects a single column, e.g.
+/// `select.subquery(&["user_id"])?`.
+pub fn in_subquery<'a, C: ColumnRef<'a>>(column: C, subquery: RawSql<'a>) -> C::Predicate {
+    let sql = format!("{} IN (", column.column_name());
+    subquery_predicate(&sql, subquery, ")").into()
+}
+
+pub fn not_in_subquery<'a, C: ColumnRef<'a>>(column: C, subquery: RawSql<'a>) -> C::Predicate {
+    let sql = format!("{} NOT IN (", column.column_name());
+    subquery_predicate(&sql, subquery, ")").into()
+}
+
+/// Compares `column` to a subquery returning at most one row and column,
+/// e.g. `compare_subquery("price", ">", select.subquery(&["avg(price)"])?)`.
+pub fn compare_subquery<'a, C: ColumnRef<'a>>(
+    column: C,
+    condition: &str,
+    subquery: RawSql<'a>,
+) -> C::Predicate {
+    let sql = format!("{} {} (", column.column_name(), condition);
+    subquery_predicate(&sql, subquery, ")").into()
+}
+
+fn subquery_predicate<'a>(prefix: &str, subquery: RawSql<'a>, suffix: &str) -> WherePredicate<'a> {
+    let raw = RawSql::new(prefix.to_string())
+        .append(subquery)
+        .push(suffix);
+    WherePredicate::Value(Expr::Raw(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::Executor;
+    use crate::pagination::CursorCodec;
+    use crate::predicates::{and, equals, raw};
+    use chrono::NaiveDateTime;
+    use dojo_macros::Model;
+    use uuid::Uuid;
+
+    #[derive(Debug, Model)]
+    #[dojo(name = "orders", sort_keys = ["created_at", "id"])]
+    struct Order {
+        id: Uuid,
+        user_id: Uuid,
+        total: i32,
+        created_at: NaiveDateTime,
+    }
+
+    #[tokio::test]
+    async fn test_subquery_params_are_renumbered() -> anyhow::Result<()> {
+        // the pool is never touched while building sql
+        let manager = bb8_postgres::PostgresConnectionManager::new(
+            "host=localhost".parse().unwrap(),
+            tokio_postgres::NoTls,
+        );
+        let pool = bb8::Pool::builder().build_unchecked(manager);
+        let codec = CursorCodec::default();
+
+        let mut orders = SelectOperation::<Order>::new(Executor::Pool(&pool), None, &codec);
+        let orders = orders
+            .where_by(raw("orders.user_id = users.id"))
+            .where_by(equals("total", &100));
+        let mut totals = SelectOperation::<Order>::new(Executor::Pool(&pool), None, &codec);
+        let totals = totals.where_by(equals("total", &50));
+
+        let predicates = [
+            equals("name", &"linh"),
+            exists(orders)?,
+            in_subquery("id", totals.subquery(&["user_id"])?),
+            compare_subquery("age", ">", totals.subquery(&["avg(total)"])?),
+            equals("email", &"linh@gmail.com"),
+        ];
+        let predicates = and(&predicates);
+
+        let (query, params) = predicates.to_sql(&mut 1);
+        assert_eq!(
+            query.unwrap(),
+            "(name = $1 AND EXISTS (SELECT 1 FROM orders WHERE orders.user_id = users.id AND total = $2) AND id IN (SELECT user_id FROM orders WHERE total = $3) AND age > (SELECT avg(total) FROM orders WHERE total = $4) AND email = $5)"
+        );
+        assert_eq!(params.len(), 5);
+
+        // a select that doesn't build can't be nested either
+        let mut latest = SelectOperation::<Order>::new(Executor::Pool(&pool), None, &codec);
+        let latest = latest.distinct_on(&[]);
+        assert!(exists(latest).is_err());
+        assert!(latest.subquery(&["id"]).is_err());
+
+        Ok(())
+    }
+}
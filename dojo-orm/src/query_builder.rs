@@ -1,16 +1,33 @@
 use futures_util::TryFutureExt;
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
 use std::rc::Rc;
 
 use crate::model::Model;
-use crate::order_by::{Direction, OrderPredicate};
-use crate::pagination::{Cursor, DefaultSortKeys, Pagination, Row};
+use crate::order_by::{Direction, OrderPredicate, SortKey};
+use crate::pagination::{is_backward, Cursor, DefaultSortKeys, Pagination, Row};
 use crate::pool::*;
 use crate::predicates::{Expr, ExprValueType, WherePredicate};
+use crate::raw::RawSql;
 use crate::types::ToSql;
 use typed_builder::TypedBuilder;
 
+/// Postgres counts the bind parameters of a statement in 16 bits.
+pub(crate) const MAX_BIND_PARAMS: usize = u16::MAX as usize;
+
+/// Splits the params of a multi-row insert into batches of whole rows which,
+/// with the `extra` params every statement repeats, stay under
+/// [`MAX_BIND_PARAMS`].
+pub(crate) fn insert_batches<'p, 'q>(
+    params: &'p [&'q (dyn ToSql + Sync)],
+    row_len: usize,
+    extra: usize,
+) -> std::slice::Chunks<'p, &'q (dyn ToSql + Sync)> {
+    let row_len = row_len.max(1);
+    let rows = (MAX_BIND_PARAMS.saturating_sub(extra) / row_len).max(1);
+    params.chunks(rows * row_len)
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum QueryType {
     Select,
@@ -20,6 +37,48 @@ pub enum QueryType {
     Update,
 }
 
+/// The row lock taken by `SELECT ... FOR <strength>`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LockStrength {
+    Update,
+    NoKeyUpdate,
+    Share,
+}
+
+impl Display for LockStrength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockStrength::Update => write!(f, "FOR UPDATE"),
+            LockStrength::NoKeyUpdate => write!(f, "FOR NO KEY UPDATE"),
+            LockStrength::Share => write!(f, "FOR SHARE"),
+        }
+    }
+}
+
+/// What a locking select does with rows another transaction has locked.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum LockWait {
+    #[default]
+    Wait,
+    NoWait,
+    SkipLocked,
+}
+
+/// `SELECT DISTINCT` over every selected column, or `DISTINCT ON` some of them.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Distinct<'a> {
+    Rows,
+    On(&'a [&'a str]),
+}
+
+/// A common table expression, selected from by naming it as the table.
+#[derive(Debug, Clone)]
+pub struct Cte<'a> {
+    pub name: &'a str,
+    pub query: RawSql<'a>,
+    pub recursive: bool,
+}
+
 #[derive(TypedBuilder, Debug)]
 pub struct QueryBuilder<'a> {
     pub table_name: &'a str,
@@ -28,8 +87,14 @@ pub struct QueryBuilder<'a> {
     #[builder(default = Vec::new())]
     pub default_keys: Vec<String>,
     #[builder(default = & [])]
+    pub nullable_columns: &'a [&'a str],
+    #[builder(default = & [])]
     pub params: &'a [&'a (dyn ToSql + Sync)],
     #[builder(default = & [])]
+    pub set_exprs: &'a [(&'a str, RawSql<'a>)],
+    #[builder(default = & [])]
+    pub ctes: &'a [Cte<'a>],
+    #[builder(default = & [])]
     pub where_predicates: &'a [WherePredicate<'a>],
     #[builder(default = & [])]
     pub order_by_predicates: &'a [OrderPredicate<'a>],
@@ -45,8 +110,16 @@ pub struct QueryBuilder<'a> {
     pub is_returning: bool,
     #[builder(default = & [])]
     pub returning: &'a [&'a str],
-    #[builder(default = None, setter(strip_option))]
+    #[builder(default = None)]
     pub limit: Option<i64>,
+    #[builder(default = None)]
+    pub offset: Option<i64>,
+    #[builder(default = None)]
+    pub distinct: Option<Distinct<'a>>,
+    #[builder(default = None)]
+    pub lock: Option<LockStrength>,
+    #[builder(default)]
+    pub lock_wait: LockWait,
     pub ty: QueryType,
     #[builder(default = false)]
     pub on_conflict: bool,
@@ -54,23 +127,74 @@ pub struct QueryBuilder<'a> {
     pub conflict_target: Option<&'a [&'a str]>,
     #[builder(default = None, setter(strip_option))]
     pub conflict_update: Option<&'a [(&'a str, &'a (dyn ToSql + Sync))]>,
+    #[builder(default = None, setter(strip_option))]
+    pub insert_select: Option<&'a QueryBuilder<'a>>,
 }
 
 impl<'a> QueryBuilder<'a> {
     pub fn build_limit_sql(&self) -> String {
-        let mut stmt = " LIMIT ".to_string();
+        let mut stmt = "".to_string();
         let limit = if self.ty == QueryType::Select {
-            self.limit.unwrap_or(20)
+            self.limit
         } else {
-            self.first.or(self.last).unwrap_or(20) + 1
+            // one extra row tells whether there is another page
+            self.first.or(self.last).map(|limit| limit + 1)
         };
 
-        stmt.push_str(&format!("{}", limit));
+        if let Some(limit) = limit {
+            stmt.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        if let Some(offset) = self.offset {
+            stmt.push_str(&format!(" OFFSET {}", offset));
+        }
+
         stmt
     }
 
+    pub fn is_backward(&self) -> bool {
+        is_backward(self.first, self.last, self.before.is_some())
+    }
+
+    // Keys a page is sorted and sliced by: the explicit ordering followed by
+    // the default keys as tie breakers, flipped when paging backward.
+    pub fn build_sort_keys(&self) -> anyhow::Result<Vec<SortKey<'_>>> {
+        let mut keys: Vec<SortKey> = vec![];
+        for op in self.order_by_predicates {
+            let direction = match op {
+                OrderPredicate::Asc(_) => Direction::Asc,
+                OrderPredicate::Desc(_) => Direction::Desc,
+                OrderPredicate::Nearest(column, _) => {
+                    return Err(anyhow::anyhow!(
+                        "cursor pagination can't order by nearest on {}",
+                        column
+                    ));
+                }
+            };
+
+            keys.push(SortKey::new(op.column(), direction));
+        }
+
+        for key in &self.default_keys {
+            if !keys.iter().any(|k| k.column == key) {
+                keys.push(SortKey::new(key, Direction::Asc));
+            }
+        }
+
+        let backward = self.is_backward();
+        for key in keys.iter_mut() {
+            key.nullable = self.nullable_columns.contains(&key.column);
+            if backward {
+                key.direction = key.direction.reverse();
+            }
+        }
+
+        Ok(keys)
+    }
+
     pub fn build_order_by_sql<'b>(
-        &'a self,
+        &self,
+        sort_keys: &[SortKey],
         params_index: &'b mut usize,
     ) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
         let mut stmt = "".to_string();
@@ -78,30 +202,23 @@ impl<'a> QueryBuilder<'a> {
         let mut order_by = vec![];
 
         if self.ty == QueryType::Paging {
-            let direction = if self.first.is_some() {
-                Direction::Asc
-            } else {
-                Direction::Desc
-            };
-
-            for (i, key) in self.default_keys.iter().enumerate() {
-                let direction = if i == 0 { direction } else { Direction::Asc };
-                order_by.push(format!("{} {}", key, direction));
+            for key in sort_keys {
+                order_by.push(format!("{} {}", key.column, key.direction));
             }
-        }
-
-        for op in self.order_by_predicates {
-            match op {
-                OrderPredicate::Asc(column) => {
-                    order_by.push(format!("{} ASC", column));
-                }
-                OrderPredicate::Desc(column) => {
-                    order_by.push(format!("{} DESC", column));
-                }
-                OrderPredicate::Nearest(column, vector) => {
-                    order_by.push(format!("{} <-> ${}", column, params_index));
-                    params.push(vector);
-                    *params_index += 1;
+        } else {
+            for op in self.order_by_predicates {
+                match op {
+                    OrderPredicate::Asc(column) => {
+                        order_by.push(format!("{} ASC", column));
+                    }
+                    OrderPredicate::Desc(column) => {
+                        order_by.push(format!("{} DESC", column));
+                    }
+                    OrderPredicate::Nearest(column, vector) => {
+                        order_by.push(format!("{} <-> ${}", column, params_index));
+                        params.push(vector);
+                        *params_index += 1;
+                    }
                 }
             }
         }
@@ -115,21 +232,18 @@ impl<'a> QueryBuilder<'a> {
     }
 
     pub fn build_where_sql<'b>(
-        &'a self,
+        &self,
+        sort_keys: &[SortKey],
         params_index: &'b mut usize,
-    ) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+    ) -> anyhow::Result<(String, Vec<&'a (dyn ToSql + Sync)>)> {
         let mut params = self.params.to_vec();
         let mut stmt = "".to_string();
         let mut predicates_str = vec![];
 
-        if let Some(before) = self.before {
-            let (before_sql, before_params) = before.to_where_stmt(Direction::Desc, params_index);
-            predicates_str.push(before_sql);
-            params.extend(before_params);
-        } else if let Some(after) = self.after {
-            let (after_sql, after_params) = after.to_where_stmt(Direction::Asc, params_index);
-            predicates_str.push(after_sql);
-            params.extend(after_params);
+        if let Some(cursor) = self.before.as_ref().or(self.after.as_ref()) {
+            let (cursor_sql, cursor_params) = cursor.to_where_stmt(sort_keys, params_index)?;
+            predicates_str.push(cursor_sql);
+            params.extend(cursor_params);
         }
 
         for predicate in self.where_predicates {
@@ -144,11 +258,46 @@ impl<'a> QueryBuilder<'a> {
             stmt.push_str(&predicates_str.join(" AND "));
         }
 
+        Ok((stmt, params))
+    }
+
+    pub fn build_with_sql(
+        &self,
+        params_index: &mut usize,
+    ) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+        if self.ctes.is_empty() {
+            return ("".to_string(), vec![]);
+        }
+
+        // RECURSIVE applies to the whole list, and lets any entry refer to itself
+        let mut stmt = if self.ctes.iter().any(|cte| cte.recursive) {
+            "WITH RECURSIVE ".to_string()
+        } else {
+            "WITH ".to_string()
+        };
+
+        let mut ctes = vec![];
+        let mut params = vec![];
+        for cte in self.ctes {
+            let (cte_sql, cte_params) = cte.query.to_sql(params_index);
+            ctes.push(format!("{} AS ({})", cte.name, cte_sql));
+            params.extend(cte_params);
+        }
+        stmt.push_str(&ctes.join(", "));
+        stmt.push(' ');
+
         (stmt, params)
     }
 
     pub fn build_select_from_sql(&self) -> String {
         let mut stmt = "SELECT ".to_string();
+        match self.distinct {
+            Some(Distinct::Rows) => stmt.push_str("DISTINCT "),
+            Some(Distinct::On(columns)) => {
+                stmt.push_str(&format!("DISTINCT ON ({}) ", columns.join(", ")))
+            }
+            None => {}
+        }
         stmt.push_str(&self.columns.join(", "));
         stmt.push_str(" FROM ");
         stmt.push_str(self.table_name);
@@ -170,66 +319,157 @@ impl<'a> QueryBuilder<'a> {
         stmt
     }
 
-    pub fn build_update_set_sql(&self, params_index: &mut usize) -> String {
+    pub fn build_update_set_sql(
+        &self,
+        params_index: &mut usize,
+    ) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
         let mut stmt = " SET ".to_string();
         let mut sets = vec![];
         for column in self.columns {
             sets.push(format!("{} = ${}", column, params_index));
             *params_index += 1;
         }
+
+        let mut params = vec![];
+        for (column, expr) in self.set_exprs {
+            let (expr_sql, expr_params) = expr.to_sql(params_index);
+            sets.push(format!("{} = {}", column, expr_sql));
+            params.extend(expr_params);
+        }
         stmt.push_str(&sets.join(", "));
 
-        stmt
+        (stmt, params)
     }
 
-    pub fn build_select_sql(&'a self) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+    pub fn build_select_sql(&self) -> anyhow::Result<(String, Vec<&'a (dyn ToSql + Sync)>)> {
         let mut params_index = 1;
-        let mut stmt = self.build_select_from_sql();
+        let (mut stmt, with_params) = self.build_with_sql(&mut params_index);
+        stmt.push_str(&self.build_select_from_sql());
+
+        let sort_keys = if self.ty == QueryType::Paging {
+            self.build_sort_keys()?
+        } else {
+            vec![]
+        };
 
-        let (where_sql, where_params) = self.build_where_sql(&mut params_index);
+        self.check_distinct_on(&sort_keys)?;
+
+        // Postgres refuses row locks on a distinct select, and ignores them on
+        // a cte of one, so they would lock nothing
+        if self.distinct.is_some() && self.lock.is_some() {
+            return Err(anyhow::anyhow!(
+                "distinct and distinct on can't be combined with a row lock, e.g. for_update"
+            ));
+        }
+
+        let (where_sql, where_params) = self.build_where_sql(&sort_keys, &mut params_index)?;
         stmt.push_str(&where_sql);
 
-        let (order_by_sql, order_by_params) = self.build_order_by_sql(&mut params_index);
+        let (order_by_sql, order_by_params) =
+            self.build_order_by_sql(&sort_keys, &mut params_index);
         stmt.push_str(&order_by_sql);
 
         let limit_sql = self.build_limit_sql();
         stmt.push_str(&limit_sql);
 
-        let params = [where_params, order_by_params].concat();
+        let lock_sql = self.build_lock_sql()?;
+        stmt.push_str(&lock_sql);
 
-        (stmt, params)
+        let params = [with_params, where_params, order_by_params].concat();
+
+        Ok((stmt, params))
+    }
+
+    // Postgres keeps the first row of each DISTINCT ON group in ORDER BY
+    // order, so the leading ORDER BY columns have to be the DISTINCT ON ones
+    pub fn check_distinct_on(&self, sort_keys: &[SortKey]) -> anyhow::Result<()> {
+        let Some(Distinct::On(columns)) = self.distinct else {
+            return Ok(());
+        };
+
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("distinct on needs at least one column"));
+        }
+
+        let order_by = if self.ty == QueryType::Paging {
+            sort_keys.iter().map(|key| key.column).collect::<Vec<_>>()
+        } else {
+            self.order_by_predicates
+                .iter()
+                .map(|op| op.column())
+                .collect::<Vec<_>>()
+        };
+
+        if let Some(column) = order_by
+            .iter()
+            .take(columns.len())
+            .find(|column| !columns.contains(column))
+        {
+            return Err(anyhow::anyhow!(
+                "distinct on ({}) has to match the leading order by columns, found {}",
+                columns.join(", "),
+                column
+            ));
+        }
+
+        Ok(())
     }
 
-    pub fn build_delete_sql(&self) -> (String, Vec<&(dyn ToSql + Sync)>) {
+    pub fn build_lock_sql(&self) -> anyhow::Result<String> {
+        let Some(lock) = self.lock else {
+            if self.lock_wait != LockWait::Wait {
+                return Err(anyhow::anyhow!(
+                    "nowait and skip_locked need a row lock, e.g. for_update"
+                ));
+            }
+
+            return Ok("".to_string());
+        };
+
+        let mut stmt = format!(" {}", lock);
+        match self.lock_wait {
+            LockWait::Wait => {}
+            LockWait::NoWait => stmt.push_str(" NOWAIT"),
+            LockWait::SkipLocked => stmt.push_str(" SKIP LOCKED"),
+        }
+
+        Ok(stmt)
+    }
+
+    pub fn build_delete_sql(&self) -> anyhow::Result<(String, Vec<&(dyn ToSql + Sync)>)> {
         let mut params_index = 1;
         let mut stmt = self.build_delete_from_sql();
 
-        let (where_sql, params) = self.build_where_sql(&mut params_index);
+        let (where_sql, params) = self.build_where_sql(&[], &mut params_index)?;
         stmt.push_str(&where_sql);
 
         let returning_sql = self.build_returning_sql();
         stmt.push_str(&returning_sql);
 
-        (stmt, params)
+        Ok((stmt, params))
     }
 
-    pub fn build_update_sql(&self) -> (String, Vec<&(dyn ToSql + Sync)>) {
+    pub fn build_update_sql(&self) -> anyhow::Result<(String, Vec<&(dyn ToSql + Sync)>)> {
         let mut params_index = 1;
         let mut stmt = self.build_update_from_sql();
 
-        let set_sql = self.build_update_set_sql(&mut params_index);
+        let (set_sql, set_params) = self.build_update_set_sql(&mut params_index);
         stmt.push_str(&set_sql);
 
-        let (where_sql, params) = self.build_where_sql(&mut params_index);
+        let (where_sql, where_params) = self.build_where_sql(&[], &mut params_index)?;
         stmt.push_str(&where_sql);
 
+        // the where params start with the values of the plain SET columns
+        let (values, where_params) = where_params.split_at(self.params.len());
+        let params = [values, &set_params, where_params].concat();
+
         let returning_sql = self.build_returning_sql();
         stmt.push_str(&returning_sql);
 
-        (stmt, params)
+        Ok((stmt, params))
     }
 
-    pub fn build_insert_sql(&self) -> (String, Vec<&(dyn ToSql + Sync)>) {
+    pub fn build_insert_sql(&self) -> anyhow::Result<(String, Vec<&(dyn ToSql + Sync)>)> {
         let mut stmt = "INSERT INTO ".to_string();
         stmt.push_str(self.table_name);
 
@@ -237,31 +477,49 @@ impl<'a> QueryBuilder<'a> {
         for column in self.columns {
             columns.push(column.to_string());
         }
-        stmt.push_str(&format!(" ({}) VALUES ", columns.join(", ")));
+        stmt.push_str(&format!(" ({}) ", columns.join(", ")));
 
-        let chunks = self.params.chunks(self.columns.len()).collect::<Vec<_>>();
+        let mut params = vec![];
         let mut params_index = 1;
-        let mut values = vec![];
-        for chunk in chunks {
-            let mut values_str = vec![];
-            for _ in chunk {
-                values_str.push(format!("${}", params_index));
-                params_index += 1;
+        if let Some(select) = self.insert_select {
+            if select.columns.len() != self.columns.len() {
+                return Err(anyhow::anyhow!(
+                    "insert into {} has {} columns but its select has {}",
+                    self.table_name,
+                    self.columns.len(),
+                    select.columns.len()
+                ));
             }
-            values.push(format!("({})", values_str.join(", ")));
+
+            let (select_sql, select_params) = select.build_select_sql()?;
+            stmt.push_str(&select_sql);
+            params_index += select_params.len();
+            params.extend(select_params);
+        } else {
+            stmt.push_str("VALUES ");
+
+            let chunks = self.params.chunks(self.columns.len()).collect::<Vec<_>>();
+            let mut values = vec![];
+            for chunk in chunks {
+                let mut values_str = vec![];
+                for _ in chunk {
+                    values_str.push(format!("${}", params_index));
+                    params_index += 1;
+                }
+                values.push(format!("({})", values_str.join(", ")));
+            }
+            stmt.push_str(&values.join(", "));
+            params.extend_from_slice(self.params);
         }
-        stmt.push_str(&values.join(", "));
 
         let (on_conflict_sql, on_conflict_params) = self.build_on_conflict(&mut params_index);
         stmt.push_str(&on_conflict_sql);
-
-        let mut params = self.params.to_vec();
         params.extend(on_conflict_params);
 
         let returning_sql = self.build_returning_sql();
         stmt.push_str(&returning_sql);
 
-        (stmt, params)
+        Ok((stmt, params))
     }
 
     pub fn build_on_conflict(
@@ -273,7 +531,7 @@ impl<'a> QueryBuilder<'a> {
         if let Some(conflict_target) = self.conflict_target {
             stmt.push_str(" ON CONFLICT (");
             stmt.push_str(&conflict_target.join(", "));
-            stmt.push_str(")");
+            stmt.push(')');
         }
 
         let mut params = vec![];
@@ -324,11 +582,11 @@ impl<'a> QueryBuilder<'a> {
         }
 
         let (stmt, params) = match self.ty {
-            QueryType::Select => self.build_select_sql(),
-            QueryType::Paging => self.build_select_sql(),
-            QueryType::Delete => self.build_delete_sql(),
-            QueryType::Insert => self.build_insert_sql(),
-            QueryType::Update => self.build_update_sql(),
+            QueryType::Select => self.build_select_sql()?,
+            QueryType::Paging => self.build_select_sql()?,
+            QueryType::Delete => self.build_delete_sql()?,
+            QueryType::Insert => self.build_insert_sql()?,
+            QueryType::Update => self.build_update_sql()?,
         };
 
         Ok((stmt, params))
@@ -346,18 +604,16 @@ mod tests {
     use uuid::Uuid;
 
     fn cursor() -> Cursor {
-        Cursor {
-            values: vec![
-                Row::new(
-                    "created_at".to_string(),
-                    Value::NaiveDateTime(Utc::now().naive_utc()),
-                ),
-                Row::new(
-                    "id".to_string(),
-                    Value::Uuid(Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()),
-                ),
-            ],
-        }
+        Cursor::new(vec![
+            Row::new(
+                "created_at".to_string(),
+                Value::NaiveDateTime(Utc::now().naive_utc()),
+            ),
+            Row::new(
+                "id".to_string(),
+                Value::Uuid(Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()),
+            ),
+        ])
     }
 
     #[tokio::test]
@@ -372,7 +628,7 @@ mod tests {
         let (stmt, params) = qb.build_sql()?;
         assert_eq!(
             stmt,
-            "SELECT id, name, age, created_at FROM users ORDER BY created_at DESC, id ASC LIMIT 21"
+            "SELECT id, name, age, created_at FROM users ORDER BY created_at ASC, id ASC"
         );
         assert_eq!(params.len(), 0);
 
@@ -439,7 +695,7 @@ mod tests {
         let (stmt, params) = qb.build_sql()?;
         assert_eq!(
             stmt,
-            "SELECT id, name, age, created_at FROM users WHERE (created_at, id) < ($1, $2) ORDER BY created_at DESC, id ASC LIMIT 11"
+            "SELECT id, name, age, created_at FROM users WHERE (created_at, id) < ($1, $2) ORDER BY created_at DESC, id DESC LIMIT 11"
         );
         assert_eq!(params.len(), 2);
 
@@ -462,13 +718,73 @@ mod tests {
         let (stmt, params) = qb.build_sql()?;
         assert_eq!(
             stmt,
-            "SELECT id, name, age, created_at FROM users WHERE (created_at, id) < ($1, $2) AND name = $3 AND age = $4 ORDER BY created_at DESC, id ASC LIMIT 11"
+            "SELECT id, name, age, created_at FROM users WHERE (created_at, id) < ($1, $2) AND name = $3 AND age = $4 ORDER BY created_at DESC, id DESC LIMIT 11"
         );
         assert_eq!(params.len(), 4);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_build_first_after_with_order_by_query() -> anyhow::Result<()> {
+        let cursor = Some(Cursor::new(vec![
+            Row::new("name".to_string(), Value::String("test".to_string())),
+            Row::new(
+                "id".to_string(),
+                Value::Uuid(Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()),
+            ),
+        ]));
+        let order_by = &[OrderPredicate::Desc("name")];
+        let qb = QueryBuilder::builder()
+            .table_name("users")
+            .columns(&["id", "name", "age", "created_at"])
+            .default_keys(vec!["id".to_string()])
+            .order_by_predicates(order_by)
+            .first(Some(10))
+            .after(&cursor)
+            .ty(QueryType::Paging)
+            .build();
+
+        let (stmt, params) = qb.build_sql()?;
+        assert_eq!(
+            stmt,
+            "SELECT id, name, age, created_at FROM users WHERE (name < $1 OR (name = $1 AND id > $2)) ORDER BY name DESC, id ASC LIMIT 11"
+        );
+        assert_eq!(params.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_last_before_with_order_by_query() -> anyhow::Result<()> {
+        let cursor = Some(Cursor::new(vec![
+            Row::new("name".to_string(), Value::String("test".to_string())),
+            Row::new(
+                "id".to_string(),
+                Value::Uuid(Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()),
+            ),
+        ]));
+        let order_by = &[OrderPredicate::Desc("name")];
+        let qb = QueryBuilder::builder()
+            .table_name("users")
+            .columns(&["id", "name", "age", "created_at"])
+            .default_keys(vec!["id".to_string()])
+            .order_by_predicates(order_by)
+            .last(Some(10))
+            .before(&cursor)
+            .ty(QueryType::Paging)
+            .build();
+
+        let (stmt, params) = qb.build_sql()?;
+        assert_eq!(
+            stmt,
+            "SELECT id, name, age, created_at FROM users WHERE (name > $1 OR (name = $1 AND id < $2)) ORDER BY name ASC, id DESC LIMIT 11"
+        );
+        assert_eq!(params.len(), 2);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_build_where_by_id() -> anyhow::Result<()> {
         let id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
@@ -484,13 +800,123 @@ mod tests {
         let (stmt, params) = qb.build_sql()?;
         assert_eq!(
             stmt,
-            "SELECT id, name, age, created_at FROM users WHERE id = $1 LIMIT 20"
+            "SELECT id, name, age, created_at FROM users WHERE id = $1"
         );
         assert_eq!(params.len(), 1);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_build_limit_offset_query() -> anyhow::Result<()> {
+        let predicates = &[equals("name", &"test")];
+        let qb = QueryBuilder::builder()
+            .table_name("users")
+            .columns(&["id", "name", "age", "created_at"])
+            .where_predicates(predicates)
+            .limit(Some(10))
+            .offset(Some(20))
+            .ty(QueryType::Select)
+            .build();
+        let (stmt, params) = qb.build_sql()?;
+        assert_eq!(
+            stmt,
+            "SELECT id, name, age, created_at FROM users WHERE name = $1 LIMIT 10 OFFSET 20"
+        );
+        assert_eq!(params.len(), 1);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(LockStrength::Update, LockWait::SkipLocked, " FOR UPDATE SKIP LOCKED")]
+    #[case(LockStrength::NoKeyUpdate, LockWait::Wait, " FOR NO KEY UPDATE")]
+    #[case(LockStrength::Share, LockWait::NoWait, " FOR SHARE NOWAIT")]
+    fn test_build_select_with_lock_sql(
+        #[case] lock: LockStrength,
+        #[case] lock_wait: LockWait,
+        #[case] expected: &str,
+    ) -> anyhow::Result<()> {
+        let qb = QueryBuilder::builder()
+            .table_name("jobs")
+            .columns(&["id"])
+            .ty(QueryType::Select)
+            .limit(Some(2))
+            .lock(Some(lock))
+            .lock_wait(lock_wait)
+            .build();
+        let (stmt, _) = qb.build_sql()?;
+        assert_eq!(stmt, format!("SELECT id FROM jobs LIMIT 2{}", expected));
+
+        let qb = QueryBuilder::builder()
+            .table_name("jobs")
+            .columns(&["id"])
+            .ty(QueryType::Select)
+            .lock_wait(lock_wait)
+            .build();
+        assert_eq!(qb.build_sql().is_err(), lock_wait != LockWait::Wait);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(&["user_id", "created_at"], true)]
+    #[case(&["created_at", "user_id", "id"], true)]
+    #[case(&["user_id"], true)]
+    #[case(&[], true)]
+    #[case(&["user_id", "id"], false)]
+    #[case(&["id", "user_id"], false)]
+    fn test_build_select_distinct_on_sql(
+        #[case] order_by: &[&str],
+        #[case] is_valid: bool,
+    ) -> anyhow::Result<()> {
+        let order_by = order_by
+            .iter()
+            .map(|column| OrderPredicate::Asc(column))
+            .collect::<Vec<_>>();
+        let qb = QueryBuilder::builder()
+            .table_name("orders")
+            .columns(&["id", "user_id", "created_at"])
+            .order_by_predicates(&order_by)
+            .distinct(Some(Distinct::On(&["user_id", "created_at"])))
+            .ty(QueryType::Select)
+            .build();
+
+        match qb.build_sql() {
+            Ok((stmt, _)) => {
+                assert!(is_valid);
+                assert!(stmt.starts_with(
+                    "SELECT DISTINCT ON (user_id, created_at) id, user_id, created_at FROM orders"
+                ));
+            }
+            Err(_) => assert!(!is_valid),
+        }
+
+        let qb = QueryBuilder::builder()
+            .table_name("orders")
+            .columns(&["user_id"])
+            .order_by_predicates(&order_by)
+            .distinct(Some(Distinct::Rows))
+            .ty(QueryType::Select)
+            .build();
+        let (stmt, _) = qb.build_sql()?;
+        assert!(stmt.starts_with("SELECT DISTINCT user_id FROM orders"));
+
+        for distinct in [Distinct::Rows, Distinct::On(&["user_id"])] {
+            let qb = QueryBuilder::builder()
+                .table_name("orders")
+                .columns(&["user_id"])
+                .order_by_predicates(&order_by)
+                .distinct(Some(distinct))
+                .ty(QueryType::Select)
+                .lock(Some(LockStrength::Update))
+                .build();
+            assert!(qb.build_sql().is_err());
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_build_update_sql() -> anyhow::Result<()> {
         let id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
@@ -515,6 +941,37 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_build_update_with_set_exprs_sql() -> anyhow::Result<()> {
+        let id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
+        let name = "test".to_string();
+        let color = serde_json::json!("red");
+        let params: Vec<&(dyn ToSql + Sync)> = vec![&name];
+        let set_exprs = [(
+            "detail",
+            crate::sql!("jsonb_set(detail, '{color}', $1::jsonb)", &color),
+        )];
+        let predicates = &[equals("id", &id)];
+
+        let qb = QueryBuilder::builder()
+            .table_name("products")
+            .columns(&["name"])
+            .params(&params)
+            .set_exprs(&set_exprs)
+            .where_predicates(predicates)
+            .ty(QueryType::Update)
+            .returning(&["id"])
+            .build();
+        let (stmt, params) = qb.build_sql()?;
+        assert_eq!(
+            stmt,
+            "UPDATE products SET name = $1, detail = jsonb_set(detail, '{color}', $2::jsonb) WHERE id = $3 RETURNING id"
+        );
+        assert_eq!(params.len(), 3);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_build_insert_sql() -> anyhow::Result<()> {
         let id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
@@ -595,6 +1052,105 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_build_insert_select_sql() -> anyhow::Result<()> {
+        let name = "linh".to_string();
+        let predicates = vec![equals("name", &name)];
+        let select = QueryBuilder::builder()
+            .table_name("users")
+            .columns(&["id", "name", "NOW()"])
+            .where_predicates(&predicates)
+            .ty(QueryType::Select)
+            .build();
+
+        let binding: Vec<(&str, &(dyn ToSql + Sync))> = vec![("name", &name)];
+        let qb = QueryBuilder::builder()
+            .table_name("archived_users")
+            .columns(&["id", "name", "archived_at"])
+            .is_returning(true)
+            .ty(QueryType::Insert)
+            .conflict_target(&["id"])
+            .conflict_update(&binding)
+            .insert_select(&select)
+            .build();
+        let (stmt, params) = qb.build_sql()?;
+
+        assert_eq!(
+            stmt,
+            "INSERT INTO archived_users (id, name, archived_at) SELECT id, name, NOW() FROM users WHERE name = $1 ON CONFLICT (id) DO UPDATE SET name = $2 RETURNING id, name, archived_at"
+        );
+        assert_eq!(params.len(), 2);
+
+        let qb = QueryBuilder::builder()
+            .table_name("archived_users")
+            .columns(&["id", "name"])
+            .ty(QueryType::Insert)
+            .insert_select(&select)
+            .build();
+        assert!(qb.build_sql().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_select_with_sql() -> anyhow::Result<()> {
+        let ctes = [
+            Cte {
+                name: "roots",
+                query: crate::sql!("SELECT id FROM categories WHERE name = $1", &"root"),
+                recursive: false,
+            },
+            Cte {
+                name: "tree",
+                query: crate::sql!(
+                    "SELECT id, 0 AS depth FROM roots UNION ALL SELECT c.id, t.depth + 1 FROM categories c JOIN tree t ON c.parent_id = t.id WHERE t.depth < $1",
+                    &5
+                ),
+                recursive: true,
+            },
+        ];
+        let predicates = vec![equals("depth", &2)];
+        let qb = QueryBuilder::builder()
+            .table_name("tree")
+            .columns(&["id", "depth"])
+            .ctes(&ctes)
+            .where_predicates(&predicates)
+            .ty(QueryType::Select)
+            .build();
+        let (stmt, params) = qb.build_sql()?;
+
+        assert_eq!(
+            stmt,
+            "WITH RECURSIVE roots AS (SELECT id FROM categories WHERE name = $1), tree AS (SELECT id, 0 AS depth FROM roots UNION ALL SELECT c.id, t.depth + 1 FROM categories c JOIN tree t ON c.parent_id = t.id WHERE t.depth < $2) SELECT id, depth FROM tree WHERE depth = $3"
+        );
+        assert_eq!(params.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_batches() {
+        let value = 1;
+        let params: Vec<&(dyn ToSql + Sync)> = vec![&value; 200_000];
+
+        // 65535 / 4 = 16383 rows per batch
+        let batches = insert_batches(&params, 4, 0)
+            .map(|batch| batch.len())
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![65532, 65532, 65532, 3404]);
+
+        let batches = insert_batches(&params[..8], 4, 2)
+            .map(|batch| batch.len())
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![8]);
+
+        let batches = insert_batches(&params, 5, 3).collect::<Vec<_>>();
+        assert!(batches
+            .iter()
+            .all(|batch| batch.len() + 3 <= MAX_BIND_PARAMS));
+        assert!(batches.iter().all(|batch| batch.len() % 5 == 0));
+    }
+
     #[tokio::test]
     async fn test_build_insert_many_sql() -> anyhow::Result<()> {
         let id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS dojo_jobs
+(
+    id           uuid PRIMARY KEY,
+    queue        TEXT      NOT NULL,
+    payload      jsonb     NOT NULL,
+    status       TEXT      NOT NULL,
+    attempts     int4      NOT NULL DEFAULT 0,
+    max_attempts int4      NOT NULL,
+    run_at       TIMESTAMP NOT NULL,
+    locked_until TIMESTAMP,
+    last_error   TEXT,
+    created_at   TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_dojo_jobs_queue_run_at
+    ON dojo_jobs (queue, run_at) WHERE status IN ('pending', 'running');
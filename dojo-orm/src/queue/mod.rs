@@ -0,0 +1,483 @@
+//! A job queue stored in the `dojo_jobs` table.
+//!
+//! Workers claim jobs with `SELECT ... FOR UPDATE SKIP LOCKED`, so any
+//! number of them can share a queue. A claimed job stays invisible to other
+//! workers until its visibility timeout expires, failed jobs are retried with
+//! an exponential backoff and end up dead-lettered once they run out of
+//! attempts.
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio_postgres::Row;
+use tracing::warn;
+use uuid::Uuid;
+
+use dojo_macros::{Model, Type, UpdateModel};
+
+use crate::database::Database;
+use crate::order_by::asc;
+use crate::predicates::{equals, raw};
+use crate::sql;
+
+/// Creates the `dojo_jobs` table, safe to run more than once.
+pub const MIGRATION: &str = include_str!("migration.sql");
+
+/// Channel notified with the queue name whenever a job is enqueued.
+pub const CHANNEL: &str = "dojo_jobs";
+
+// `run_at` and `locked_until` are UTC timestamps compared against the
+// database clock, whatever the clocks of the workers say
+const NOW: &str = "SELECT (now() AT TIME ZONE 'utc')";
+
+/// Returned when completing or failing a job another worker has claimed
+/// since, after its visibility timeout expired.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LostClaim {
+    pub id: Uuid,
+    pub attempts: i32,
+}
+
+impl Display for LostClaim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "job {} is no longer claimed by attempt {}",
+            self.id, self.attempts
+        )
+    }
+}
+
+impl Error for LostClaim {}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Type)]
+#[dojo(storage = "text", rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Dead,
+}
+
+#[derive(Debug, Model)]
+#[dojo(name = "dojo_jobs", sort_keys = ["run_at", "id"])]
+struct JobRow {
+    id: Uuid,
+    queue: String,
+    payload: serde_json::Value,
+    status: JobStatus,
+    attempts: i32,
+    max_attempts: i32,
+    run_at: NaiveDateTime,
+    locked_until: Option<NaiveDateTime>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, UpdateModel)]
+struct UpdateJob {
+    status: JobStatus,
+    attempts: Option<i32>,
+    run_at: Option<NaiveDateTime>,
+    #[dojo(nullable)]
+    locked_until: Option<NaiveDateTime>,
+    last_error: Option<String>,
+    updated_at: NaiveDateTime,
+}
+
+impl UpdateJob {
+    fn new(status: JobStatus) -> Self {
+        Self {
+            status,
+            attempts: None,
+            run_at: None,
+            locked_until: None,
+            last_error: None,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// A job claimed by a worker.
+#[derive(Debug)]
+pub struct Job<P> {
+    pub id: Uuid,
+    pub queue: String,
+    pub payload: P,
+    pub status: JobStatus,
+    /// Attempts so far, including the current one.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: NaiveDateTime,
+    pub last_error: Option<String>,
+}
+
+impl<P: DeserializeOwned> TryFrom<JobRow> for Job<P> {
+    type Error = serde_json::Error;
+
+    fn try_from(row: JobRow) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            queue: row.queue,
+            payload: serde_json::from_value(row.payload)?,
+            status: row.status,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            run_at: row.run_at,
+            last_error: row.last_error,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct QueueOptions {
+    /// How long a claimed job stays hidden from other workers.
+    pub visibility_timeout: Duration,
+    pub max_attempts: i32,
+    /// Delay before the first retry, doubled on every further attempt.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    /// How often an idle worker polls when no notification wakes it up.
+    pub poll_interval: Duration,
+}
+
+impl Default for QueueOptions {
+    fn default() -> Self {
+        Self {
+            visibility_timeout: Duration::from_secs(60),
+            max_attempts: 5,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60 * 60),
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl QueueOptions {
+    pub fn visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    fn backoff_for(&self, attempts: i32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1) as u32);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+#[derive(Clone)]
+pub struct Queue {
+    db: Database,
+    name: String,
+    options: QueueOptions,
+}
+
+impl Debug for Queue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Queue")
+            .field("name", &self.name)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl Queue {
+    pub fn new(db: &Database, name: impl Into<String>) -> Self {
+        Self {
+            db: db.clone(),
+            name: name.into(),
+            options: QueueOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: QueueOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Runs [`MIGRATION`].
+    pub async fn migrate(db: &Database) -> Result<()> {
+        let conn = db.get().await?;
+        conn.batch_execute(MIGRATION).await?;
+
+        Ok(())
+    }
+
+    pub async fn enqueue<P: Serialize>(&self, payload: &P) -> Result<Uuid> {
+        let now = db_time(self.db.raw_query(NOW).await?)?;
+        self.enqueue_at(payload, now).await
+    }
+
+    /// Enqueues a job that won't be claimed before `run_at` (UTC).
+    pub async fn enqueue_at<P: Serialize>(
+        &self,
+        payload: &P,
+        run_at: NaiveDateTime,
+    ) -> Result<Uuid> {
+        let now = Utc::now().naive_utc();
+        let row = JobRow {
+            id: Uuid::new_v4(),
+            queue: self.name.clone(),
+            payload: serde_json::to_value(payload)?,
+            status: JobStatus::Pending,
+            attempts: 0,
+            max_attempts: self.options.max_attempts,
+            run_at,
+            locked_until: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.db.insert(&[&row]).all().await?;
+
+        self.db.notify(CHANNEL, &self.name).await?;
+
+        Ok(row.id)
+    }
+
+    /// Claims the next due job, if any.
+    ///
+    /// Jobs whose payload doesn't deserialize into `P`, or whose last attempt
+    /// timed out with no attempts left, are dead-lettered on the way.
+    pub async fn fetch<P: DeserializeOwned>(&self) -> Result<Option<Job<P>>> {
+        loop {
+            let tx = self.db.begin().await?;
+            let now = db_time(tx.raw_query(NOW).await?)?;
+            let row = tx
+                .bind::<JobRow>()
+                .where_by(equals("queue", &self.name))
+                .where_by(raw(sql!(
+                    "((status = 'pending' AND run_at <= $1) OR (status = 'running' AND locked_until <= $1))",
+                    &now
+                )))
+                .order_by(asc("run_at"))
+                .limit(1)
+                .for_update()
+                .skip_locked()
+                .first()
+                .await?;
+            let Some(row) = row else {
+                tx.commit().await?;
+                return Ok(None);
+            };
+
+            if row.status == JobStatus::Running && row.attempts >= row.max_attempts {
+                let mut update = UpdateJob::new(JobStatus::Dead);
+                update.last_error = Some("visibility timeout expired".to_string());
+                tx.update::<JobRow, UpdateJob>(&update)
+                    .where_by(equals("id", &row.id))
+                    .exec()
+                    .await?;
+                tx.commit().await?;
+                continue;
+            }
+
+            let locked_until = now + chrono::Duration::from_std(self.options.visibility_timeout)?;
+            let mut update = UpdateJob::new(JobStatus::Running);
+            update.attempts = Some(row.attempts + 1);
+            update.locked_until = Some(locked_until);
+            let row = tx
+                .update::<JobRow, UpdateJob>(&update)
+                .where_by(equals("id", &row.id))
+                .exec()
+                .await?;
+
+            let id = row.id;
+            match Job::try_from(row) {
+                Ok(job) => {
+                    tx.commit().await?;
+                    return Ok(Some(job));
+                }
+                Err(e) => {
+                    warn!(%id, error = %e, "dead-lettering job with an invalid payload");
+                    let mut update = UpdateJob::new(JobStatus::Dead);
+                    update.last_error = Some(format!("invalid payload: {}", e));
+                    tx.update::<JobRow, UpdateJob>(&update)
+                        .where_by(equals("id", &id))
+                        .exec()
+                        .await?;
+                    tx.commit().await?;
+                }
+            }
+        }
+    }
+
+    /// Fails with [`LostClaim`] if the job was claimed again in the meantime.
+    pub async fn complete<P>(&self, job: &Job<P>) -> Result<()> {
+        self.update_claimed(job.id, job.attempts, &UpdateJob::new(JobStatus::Done))
+            .await
+    }
+
+    /// Schedules a retry after the backoff, or dead-letters the job once it
+    /// has used all its attempts. Fails with [`LostClaim`] if the job was
+    /// claimed again in the meantime.
+    pub async fn fail<P>(&self, job: &Job<P>, error: impl Display) -> Result<()> {
+        self.fail_by(job.id, job.attempts, job.max_attempts, error)
+            .await
+    }
+
+    async fn fail_by(
+        &self,
+        id: Uuid,
+        attempts: i32,
+        max_attempts: i32,
+        error: impl Display,
+    ) -> Result<()> {
+        let mut update = if attempts >= max_attempts {
+            UpdateJob::new(JobStatus::Dead)
+        } else {
+            let backoff = chrono::Duration::from_std(self.options.backoff_for(attempts))?;
+            let mut update = UpdateJob::new(JobStatus::Pending);
+            update.run_at = Some(db_time(self.db.raw_query(NOW).await?)? + backoff);
+            update
+        };
+        update.last_error = Some(error.to_string());
+
+        self.update_claimed(id, attempts, &update).await
+    }
+
+    // Only the worker holding the attempt it claimed may move the job on, a
+    // slow worker whose job timed out and was claimed again matches no row.
+    async fn update_claimed(&self, id: Uuid, attempts: i32, update: &UpdateJob) -> Result<()> {
+        let row = self
+            .db
+            .update::<JobRow, UpdateJob>(update)
+            .where_by(equals("id", &id))
+            .where_by(equals("status", &JobStatus::Running))
+            .where_by(equals("attempts", &attempts))
+            .first()
+            .await?;
+
+        match row {
+            Some(_) => Ok(()),
+            None => Err(LostClaim { id, attempts }.into()),
+        }
+    }
+
+    /// Jobs of this queue that ran out of attempts. Payloads are left as
+    /// JSON, a job may be dead-lettered because it doesn't deserialize.
+    pub async fn dead_letters(&self) -> Result<Vec<Job<serde_json::Value>>> {
+        let rows = self
+            .db
+            .bind::<JobRow>()
+            .where_by(equals("queue", &self.name))
+            .where_by(equals("status", &JobStatus::Dead))
+            .order_by(asc("run_at"))
+            .all()
+            .await?;
+
+        rows.into_iter()
+            .map(|row| Job::try_from(row).map_err(Into::into))
+            .collect()
+    }
+
+    /// Puts a dead-lettered job back in the queue with fresh attempts. Fails
+    /// if the job isn't dead anymore, e.g. it was retried already.
+    pub async fn retry_dead<P>(&self, job: &Job<P>) -> Result<()> {
+        let mut update = UpdateJob::new(JobStatus::Pending);
+        update.attempts = Some(0);
+        update.run_at = Some(db_time(self.db.raw_query(NOW).await?)?);
+        let row = self
+            .db
+            .update::<JobRow, UpdateJob>(&update)
+            .where_by(equals("id", &job.id))
+            .where_by(equals("status", &JobStatus::Dead))
+            .first()
+            .await?;
+        if row.is_none() {
+            return Err(anyhow::anyhow!("job {} is not dead-lettered", job.id));
+        }
+
+        self.db.notify(CHANNEL, &self.name).await?;
+
+        Ok(())
+    }
+
+    /// Runs `handler` on every job of the queue, forever.
+    ///
+    /// Jobs are completed when the handler succeeds and failed when it
+    /// returns an error, unless they were claimed again after timing out, in
+    /// which case the outcome is dropped. Between jobs the worker sleeps until a job is
+    /// enqueued or the poll interval elapses.
+    pub async fn work<P, F, Fut>(&self, handler: F) -> Result<()>
+    where
+        P: DeserializeOwned,
+        F: Fn(Job<P>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut notifications = self.db.listen(&[CHANNEL]).await?;
+
+        loop {
+            while let Some(job) = self.fetch::<P>().await? {
+                let (id, attempts, max_attempts) = (job.id, job.attempts, job.max_attempts);
+                let result = match handler(job).await {
+                    Ok(()) => {
+                        let update = UpdateJob::new(JobStatus::Done);
+                        self.update_claimed(id, attempts, &update).await
+                    }
+                    Err(e) => self.fail_by(id, attempts, max_attempts, e).await,
+                };
+                match result {
+                    Err(e) if e.is::<LostClaim>() => warn!(%id, "{}", e),
+                    result => result?,
+                }
+            }
+
+            // a failed reconnect is retried by the stream, polling covers the gap
+            let _ = tokio::time::timeout(self.options.poll_interval, notifications.next()).await;
+        }
+    }
+}
+
+fn db_time(rows: Vec<Row>) -> Result<NaiveDateTime> {
+    let row = rows
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("the database returned no time"))?;
+
+    Ok(row.try_get(0)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let options =
+            QueueOptions::default().backoff(Duration::from_secs(2), Duration::from_secs(10));
+
+        assert_eq!(options.backoff_for(1), Duration::from_secs(2));
+        assert_eq!(options.backoff_for(2), Duration::from_secs(4));
+        assert_eq!(options.backoff_for(3), Duration::from_secs(8));
+        assert_eq!(options.backoff_for(4), Duration::from_secs(10));
+        assert_eq!(options.backoff_for(100), Duration::from_secs(10));
+    }
+}
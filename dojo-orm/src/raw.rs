@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+
+use crate::types::ToSql;
+
+#[macro_export]
+macro_rules! sql {
+    ($sql: expr $(,)?) => {
+        $crate::raw::RawSql::new($sql)
+    };
+    ($sql: expr, $($param: expr),+ $(,)?) => {
+        $crate::raw::RawSql::with_params(
+            $sql,
+            vec![$($param as &(dyn $crate::types::ToSql + Sync)),+],
+        )
+    };
+}
+
+/// A SQL fragment with its own positional parameters.
+///
+/// Placeholders are numbered from `$1` within the fragment and are shifted
+/// when the fragment is composed into a larger statement.
+#[derive(Debug, Clone, Default)]
+pub struct RawSql<'a> {
+    pub(crate) sql: Cow<'a, str>,
+    pub(crate) params: Vec<&'a (dyn ToSql + Sync)>,
+}
+
+impl<'a> RawSql<'a> {
+    pub fn new(sql: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            sql: sql.into(),
+            params: vec![],
+        }
+    }
+
+    pub fn with_params(sql: impl Into<Cow<'a, str>>, params: Vec<&'a (dyn ToSql + Sync)>) -> Self {
+        Self {
+            sql: sql.into(),
+            params,
+        }
+    }
+
+    pub fn push(mut self, sql: &str) -> Self {
+        self.sql.to_mut().push_str(sql);
+        self
+    }
+
+    pub fn bind<T: ToSql + Sync>(mut self, value: &'a T) -> Self {
+        self.params.push(value);
+        let placeholder = format!("${}", self.params.len());
+        self.sql.to_mut().push_str(&placeholder);
+        self
+    }
+
+    pub fn append(mut self, other: RawSql<'a>) -> Self {
+        let sql = renumber(&other.sql, self.params.len());
+        self.sql.to_mut().push_str(&sql);
+        self.params.extend(other.params);
+        self
+    }
+
+    pub fn params(&self) -> &[&'a (dyn ToSql + Sync)] {
+        &self.params
+    }
+
+    pub fn to_sql(&self, params_index: &mut usize) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+        let stmt = renumber(&self.sql, *params_index - 1);
+        *params_index += self.params.len();
+
+        (stmt, self.params.clone())
+    }
+
+    pub fn build(&self) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+        self.to_sql(&mut 1)
+    }
+}
+
+impl<'a> From<&'a str> for RawSql<'a> {
+    fn from(sql: &'a str) -> Self {
+        RawSql::new(sql)
+    }
+}
+
+impl<'a> From<String> for RawSql<'a> {
+    fn from(sql: String) -> Self {
+        RawSql::new(sql)
+    }
+}
+
+// Shifts every `$n` placeholder by `offset`, leaving quoted literals and
+// identifiers, dollar-quoted strings and comments untouched.
+fn renumber(sql: &str, offset: usize) -> String {
+    if offset == 0 {
+        return sql.to_string();
+    }
+
+    let mut stmt = String::with_capacity(sql.len());
+    let mut rest = sql;
+
+    while let Some(ch) = rest.chars().next() {
+        let len = match ch {
+            '\'' | '"' => quoted_len(rest, ch),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => block_comment_len(rest),
+            '$' => {
+                let digits = rest[1..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len() - 1);
+                if digits > 0 {
+                    // digits are all ascii numbers, so the parse can't fail
+                    let index: usize = rest[1..=digits].parse().unwrap();
+                    stmt.push_str(&format!("${}", index + offset));
+                    rest = &rest[1 + digits..];
+                    continue;
+                }
+
+                dollar_quoted_len(rest).unwrap_or(1)
+            }
+            _ => ch.len_utf8(),
+        };
+
+        stmt.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    stmt
+}
+
+// Up to the closing quote, a doubled quote reads as two literals in a row.
+fn quoted_len(sql: &str, quote: char) -> usize {
+    sql[1..].find(quote).map_or(sql.len(), |i| i + 2)
+}
+
+// Postgres lets block comments nest.
+fn block_comment_len(sql: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < sql.len() {
+        if sql[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if sql[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    sql.len()
+}
+
+// `$$...$$` or `$tag$...$tag$`, `None` when `sql` doesn't open one.
+fn dollar_quoted_len(sql: &str) -> Option<usize> {
+    let end = 1 + sql[1..].find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    if !sql[end..].starts_with('$') {
+        return None;
+    }
+
+    let tag = &sql[..=end];
+    let body = &sql[tag.len()..];
+    Some(
+        body.find(tag)
+            .map_or(sql.len(), |i| tag.len() + i + tag.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_macro() {
+        let name = "linh".to_string();
+        let raw = sql!("name = $1 AND age > $2", &name, &20);
+
+        let (stmt, params) = raw.build();
+        assert_eq!(stmt, "name = $1 AND age > $2");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_renumber_with_offset() {
+        let raw = sql!("name = $1 OR alias = $1 AND age > $2", &"linh", &20);
+
+        let (stmt, params) = raw.to_sql(&mut 4);
+        assert_eq!(stmt, "name = $4 OR alias = $4 AND age > $5");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_renumber_skips_comments() {
+        let raw = sql!(
+            "-- the $1 of the caller\nname = $1 /* or $2 /* nested $1 */ $2 */ AND age > $2",
+            &"linh",
+            &20
+        );
+
+        let (stmt, _) = raw.to_sql(&mut 3);
+        assert_eq!(
+            stmt,
+            "-- the $1 of the caller\nname = $3 /* or $2 /* nested $1 */ $2 */ AND age > $4"
+        );
+    }
+
+    #[test]
+    fn test_renumber_skips_dollar_quotes() {
+        let raw = sql!(
+            "body = $$ costs $1 $$ AND f = $fn$ SELECT $1, $$ $2 $$ $fn$ AND id = $1",
+            &20
+        );
+
+        let (stmt, _) = raw.to_sql(&mut 3);
+        assert_eq!(
+            stmt,
+            "body = $$ costs $1 $$ AND f = $fn$ SELECT $1, $$ $2 $$ $fn$ AND id = $3"
+        );
+    }
+
+    #[test]
+    fn test_renumber_skips_literals() {
+        let raw = sql!("price = '$1' AND \"$2\" = $1", &20);
+
+        let (stmt, _) = raw.to_sql(&mut 3);
+        assert_eq!(stmt, "price = '$1' AND \"$2\" = $3");
+    }
+
+    #[test]
+    fn test_compose() {
+        let name = "linh".to_string();
+        let raw = RawSql::new("SELECT * FROM users WHERE name = ")
+            .bind(&name)
+            .push(" AND ")
+            .append(sql!("age BETWEEN $1 AND $2", &18, &30));
+
+        let (stmt, params) = raw.build();
+        assert_eq!(
+            stmt,
+            "SELECT * FROM users WHERE name = $1 AND age BETWEEN $2 AND $3"
+        );
+        assert_eq!(params.len(), 3);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use anyhow::Result;
+use tokio::runtime::Handle;
+use tokio_postgres::{Client, Row};
+use tracing::warn;
+
+use crate::execution::Executor;
+use crate::model::{Model, UpdateModel};
+use crate::operations::copy;
+use crate::operations::*;
+use crate::pagination::CursorCodec;
+use crate::pool::*;
+use crate::raw::RawSql;
+use crate::MaxRows;
+use futures::stream::BoxStream;
+
+/// A transaction opened by [`crate::Database::begin`].
+///
+/// Dropping it without calling `commit` rolls it back.
+pub struct Transaction {
+    conn: Option<PooledConnection<'static, PostgresConnectionManager<NoTls>>>,
+    // the runtime that began the transaction, a drop outside of it still
+    // needs somewhere to run the rollback
+    runtime: Handle,
+    max_rows: Option<MaxRows>,
+    cursor_codec: CursorCodec,
+}
+
+impl Transaction {
+    pub(crate) fn new(
+        conn: PooledConnection<'static, PostgresConnectionManager<NoTls>>,
+        max_rows: Option<MaxRows>,
+        cursor_codec: CursorCodec,
+    ) -> Self {
+        Self {
+            conn: Some(conn),
+            runtime: Handle::current(),
+            max_rows,
+            cursor_codec,
+        }
+    }
+
+    pub(crate) async fn begin(
+        pool: &Pool<PostgresConnectionManager<NoTls>>,
+        max_rows: Option<MaxRows>,
+        cursor_codec: CursorCodec,
+    ) -> Result<Self> {
+        let conn = pool.get_owned().await?;
+        conn.batch_execute("BEGIN").await?;
+
+        Ok(Self::new(conn, max_rows, cursor_codec))
+    }
+
+    pub(crate) fn executor(&self) -> Executor<'_> {
+        Executor::Client(self.client())
+    }
+
+    fn client(&self) -> &Client {
+        // only taken by commit, rollback and drop, which all consume self
+        self.conn.as_ref().expect("transaction is already finished")
+    }
+
+    pub async fn commit(mut self) -> Result<()> {
+        if let Some(conn) = self.conn.take() {
+            conn.batch_execute("COMMIT").await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<()> {
+        if let Some(conn) = self.conn.take() {
+            roll_back(conn).await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn bind<T>(&self) -> SelectOperation<'_, T>
+    where
+        T: Model + Debug,
+    {
+        SelectOperation::new(
+            Executor::Client(self.client()),
+            self.max_rows,
+            &self.cursor_codec,
+        )
+    }
+
+    pub fn insert<'a, T>(&'a self, data: &'a [&'a T]) -> InsertOperation<'a, T>
+    where
+        T: Model + Debug,
+    {
+        InsertOperation {
+            executor: Executor::Client(self.client()),
+            data,
+        }
+    }
+
+    /// Inserts the rows `select` returns into `T`'s table, by default reading
+    /// the columns of `T` with the same names.
+    pub fn insert_from<'a, T>(
+        &'a self,
+        select: &'a SelectOperation<'a, impl Model + Debug>,
+    ) -> InsertFromOperation<'a, T>
+    where
+        T: Model + Debug,
+    {
+        InsertFromOperation {
+            executor: Executor::Client(self.client()),
+            source: select.source(),
+            columns: T::COLUMNS.to_vec(),
+            select_columns: T::COLUMNS.to_vec(),
+            _t: PhantomData,
+        }
+    }
+
+    pub fn update<'a, T, U>(&'a self, data: &'a U) -> UpdateOperation<'a, T, U>
+    where
+        T: Model + Debug,
+        U: UpdateModel,
+    {
+        UpdateOperation {
+            executor: Executor::Client(self.client()),
+            columns: data.columns(),
+            params: data.params(),
+            set_exprs: vec![],
+            predicates: vec![],
+            _t: Default::default(),
+            _u: Default::default(),
+        }
+    }
+
+    pub fn delete<T>(&self) -> DeleteOperation<'_, T>
+    where
+        T: Model + Debug,
+    {
+        DeleteOperation {
+            executor: Executor::Client(self.client()),
+            predicates: vec![],
+            _t: Default::default(),
+        }
+    }
+
+    /// Loads `rows` with a binary `COPY ... FROM STDIN`, returning the number
+    /// of rows written. Unlike `insert`, nothing is returned and there is no
+    /// limit on the number of rows.
+    pub async fn copy_in<'b, T, I>(&self, rows: I) -> Result<u64>
+    where
+        T: Model + 'b,
+        I: IntoIterator<Item = &'b T>,
+    {
+        copy::copy_in(Executor::Client(self.client()), rows).await
+    }
+
+    /// Streams every row of `T`'s table with a binary `COPY ... TO STDOUT`.
+    pub fn copy_out<'a, T>(&'a self) -> BoxStream<'a, Result<T>>
+    where
+        T: Model + Send + 'a,
+    {
+        copy::copy_out(Executor::Client(self.client()))
+    }
+
+    pub async fn raw_query<'a>(&self, sql: impl Into<RawSql<'a>>) -> Result<Vec<Row>> {
+        let (stmt, params) = sql.into().build();
+        self.client()
+            .query(&stmt, &params)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn raw_execute<'a>(&self, sql: impl Into<RawSql<'a>>) -> Result<u64> {
+        let (stmt, params) = sql.into().build();
+        self.client()
+            .execute(&stmt, &params)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+
+        // if the runtime is gone, so is the task driving the connection, and
+        // the pool throws the closed connection away
+        self.runtime.spawn(async move {
+            if let Err(e) = roll_back(conn).await {
+                warn!(?e, "failed to roll back dropped transaction");
+            }
+        });
+    }
+}
+
+// The connection goes back to the pool afterwards, which only discards closed
+// ones. If the rollback fails on a live connection, end its session rather
+// than pool a connection that is still inside the transaction.
+async fn roll_back(
+    conn: PooledConnection<'static, PostgresConnectionManager<NoTls>>,
+) -> Result<()> {
+    let result = conn.batch_execute("ROLLBACK").await;
+    if result.is_err() && !conn.is_closed() {
+        let _ = conn
+            .batch_execute("SELECT pg_terminate_backend(pg_backend_pid())")
+            .await;
+    }
+
+    Ok(result?)
+}
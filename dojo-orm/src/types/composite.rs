@@ -0,0 +1,149 @@
+//! Binary encoding helpers used by `#[derive(Type)]` on structs.
+//!
+//! A composite value is sent as the number of fields followed by each
+//! field's type OID, its length (-1 for NULL) and its own binary encoding.
+
+use std::error::Error;
+
+use bytes::{BufMut, BytesMut};
+use postgres_types::{Field, FromSql, IsNull, Kind, ToSql, Type};
+
+type BoxError = Box<dyn Error + Sync + Send>;
+type Accepts = fn(&Type) -> bool;
+
+pub fn fields(ty: &Type) -> Result<&[Field], BoxError> {
+    match ty.kind() {
+        Kind::Composite(fields) => Ok(fields),
+        _ => Err(format!("{} is not a composite type", ty.name()).into()),
+    }
+}
+
+/// Checks the composite `ty` has exactly the given fields, each of them
+/// accepted by the matching Rust field.
+pub fn accepts(ty: &Type, name: &str, expected: &[(&str, Accepts)]) -> bool {
+    if ty.name() != name {
+        return false;
+    }
+
+    let Kind::Composite(fields) = ty.kind() else {
+        return false;
+    };
+
+    fields.len() == expected.len()
+        && fields.iter().all(|field| {
+            expected
+                .iter()
+                .any(|(name, accepts)| *name == field.name() && accepts(field.type_()))
+        })
+}
+
+pub fn write_header(len: usize, out: &mut BytesMut) {
+    out.put_i32(len as i32);
+}
+
+pub fn write_field<T: ToSql>(value: &T, field: &Field, out: &mut BytesMut) -> Result<(), BoxError> {
+    out.put_u32(field.type_().oid());
+
+    let base = out.len();
+    out.put_i32(0);
+    let len = match value.to_sql(field.type_(), out)? {
+        IsNull::Yes => -1,
+        IsNull::No => (out.len() - base - 4) as i32,
+    };
+    out[base..base + 4].copy_from_slice(&len.to_be_bytes());
+
+    Ok(())
+}
+
+pub fn read_header(raw: &mut &[u8], fields: &[Field]) -> Result<(), BoxError> {
+    let len = read_i32(raw)?;
+    if len as usize != fields.len() {
+        return Err(format!("expected {} fields, got {}", fields.len(), len).into());
+    }
+
+    Ok(())
+}
+
+/// Reads the next field, checking its OID against the type Postgres
+/// reported for it.
+pub fn read_field<'a, T: FromSql<'a>>(raw: &mut &'a [u8], field: &Field) -> Result<T, BoxError> {
+    let oid = read_u32(raw)?;
+    if oid != field.type_().oid() {
+        return Err(format!(
+            "field {} has OID {}, expected {}",
+            field.name(),
+            oid,
+            field.type_().oid()
+        )
+        .into());
+    }
+
+    let len = read_i32(raw)?;
+    let value = if len < 0 {
+        None
+    } else {
+        let len = len as usize;
+        if raw.len() < len {
+            return Err("invalid composite value length".into());
+        }
+        let (value, rest) = raw.split_at(len);
+        *raw = rest;
+        Some(value)
+    };
+
+    T::from_sql_nullable(field.type_(), value)
+}
+
+pub fn read_end(raw: &[u8]) -> Result<(), BoxError> {
+    if !raw.is_empty() {
+        return Err("invalid composite value length".into());
+    }
+
+    Ok(())
+}
+
+fn read_i32(raw: &mut &[u8]) -> Result<i32, BoxError> {
+    let (bytes, rest) = raw
+        .split_first_chunk::<4>()
+        .ok_or("invalid composite value length")?;
+    *raw = rest;
+
+    Ok(i32::from_be_bytes(*bytes))
+}
+
+fn read_u32(raw: &mut &[u8]) -> Result<u32, BoxError> {
+    read_i32(raw).map(|value| value as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read_fields() -> Result<(), BoxError> {
+        let fields = vec![
+            Field::new("street".to_string(), Type::TEXT),
+            Field::new("zip".to_string(), Type::INT4),
+        ];
+
+        let mut out = BytesMut::new();
+        write_header(fields.len(), &mut out);
+        write_field(&"1 Main St", &fields[0], &mut out)?;
+        write_field(&None::<i32>, &fields[1], &mut out)?;
+
+        let mut raw = &out[..];
+        read_header(&mut raw, &fields)?;
+        let street: String = read_field(&mut raw, &fields[0])?;
+        let zip: Option<i32> = read_field(&mut raw, &fields[1])?;
+        read_end(raw)?;
+
+        assert_eq!(street, "1 Main St");
+        assert_eq!(zip, None);
+
+        let mut raw = &out[..];
+        read_header(&mut raw, &fields)?;
+        assert!(read_field::<i32>(&mut raw, &fields[1]).is_err());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+#[doc(hidden)]
+pub mod composite;
 mod pg_vector;
 
 pub use pg_vector::*;
@@ -0,0 +1,16 @@
+-- create customer table
+CREATE TYPE address AS
+(
+    street TEXT,
+    city   TEXT,
+    zip    int4
+);
+
+CREATE TABLE customers
+(
+    id                 uuid PRIMARY KEY,
+    name               TEXT      NOT NULL,
+    address            address   NOT NULL,
+    previous_addresses address[] NOT NULL DEFAULT '{}',
+    created_at         TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,12 @@
+-- create tickets table
+CREATE TYPE ticket_status AS ENUM ('open', 'in_progress', 'closed');
+
+CREATE TABLE tickets
+(
+    id         uuid PRIMARY KEY,
+    title      TEXT          NOT NULL,
+    status     ticket_status NOT NULL,
+    kind       TEXT          NOT NULL,
+    priority   SMALLINT      NOT NULL,
+    created_at TIMESTAMP     NOT NULL DEFAULT NOW()
+);
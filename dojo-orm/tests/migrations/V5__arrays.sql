@@ -0,0 +1,11 @@
+-- create article table
+CREATE TABLE articles
+(
+    id         uuid PRIMARY KEY,
+    title      TEXT            NOT NULL,
+    tags       TEXT[]          NOT NULL,
+    statuses   ticket_status[] NOT NULL,
+    ratings    SMALLINT[]      NOT NULL,
+    authors    jsonb[]         NOT NULL,
+    created_at TIMESTAMP       NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- create archived user table
+CREATE TABLE archived_users
+(
+    id          uuid PRIMARY KEY,
+    name        TEXT      NOT NULL,
+    email       TEXT      NOT NULL,
+    created_at  TIMESTAMP NOT NULL,
+    archived_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+-- create category table
+CREATE TABLE categories
+(
+    id        int4 PRIMARY KEY,
+    parent_id int4 REFERENCES categories (id),
+    name      TEXT NOT NULL
+);
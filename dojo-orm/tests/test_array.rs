@@ -0,0 +1,164 @@
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::{EmbeddedModel, Model, Type};
+use dojo_orm::prelude::*;
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+#[dojo(name = "ticket_status", rename_all = "snake_case")]
+enum Status {
+    Open,
+    InProgress,
+    Closed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+#[dojo(storage = "int", repr = i16)]
+enum Rating {
+    Bad = 1,
+    Good = 5,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, EmbeddedModel)]
+struct Author {
+    name: String,
+}
+
+#[tokio::test]
+async fn test_array() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "articles", sort_keys = ["created_at", "id"])]
+    struct Article {
+        id: Uuid,
+        title: String,
+        tags: Vec<String>,
+        statuses: Vec<Status>,
+        ratings: Vec<Rating>,
+        authors: Vec<Author>,
+        created_at: NaiveDateTime,
+    }
+
+    async fn titles(
+        db: &Database,
+        predicate: impl Into<WherePredicate<'_>>,
+    ) -> anyhow::Result<Vec<String>> {
+        let articles = db
+            .bind::<Article>()
+            .where_by(predicate.into())
+            .order_by(asc("title"))
+            .all()
+            .await?;
+
+        Ok(articles.into_iter().map(|article| article.title).collect())
+    }
+
+    let articles = [
+        (
+            "a",
+            vec!["rust", "sql"],
+            vec![Status::Open],
+            vec![Rating::Good],
+        ),
+        (
+            "b",
+            vec!["rust"],
+            vec![Status::Closed, Status::InProgress],
+            vec![Rating::Bad, Rating::Good],
+        ),
+        ("c", vec!["go"], vec![], vec![]),
+    ]
+    .into_iter()
+    .map(|(title, tags, statuses, ratings)| Article {
+        id: Uuid::new_v4(),
+        title: title.to_string(),
+        tags: tags.into_iter().map(|tag| tag.to_string()).collect(),
+        statuses,
+        ratings,
+        authors: vec![Author {
+            name: format!("{} author", title),
+        }],
+        created_at: Utc::now().naive_utc(),
+    })
+    .collect::<Vec<_>>();
+    db.insert(&articles.iter().collect::<Vec<_>>())
+        .all()
+        .await?;
+
+    let article = db
+        .bind::<Article>()
+        .where_by(equals("title", &"b"))
+        .first()
+        .await?
+        .unwrap();
+    assert_that!(
+        article.statuses,
+        elements_are![eq(Status::Closed), eq(Status::InProgress)]
+    );
+    assert_that!(
+        article.ratings,
+        elements_are![eq(Rating::Bad), eq(Rating::Good)]
+    );
+    assert_that!(
+        article.authors,
+        elements_are![eq(Author {
+            name: "b author".to_string()
+        })]
+    );
+
+    let tags = vec!["rust".to_string(), "sql".to_string()];
+    assert_that!(
+        titles(&db, array_contains("tags", &tags)).await?,
+        elements_are![eq("a")]
+    );
+    assert_that!(
+        titles(&db, array_overlaps("tags", &tags)).await?,
+        elements_are![eq("a"), eq("b")]
+    );
+    assert_that!(
+        titles(&db, array_contained_by("tags", &tags)).await?,
+        elements_are![eq("a"), eq("b")]
+    );
+    assert_that!(
+        titles(&db, array_cardinality("statuses", Comparison::Eq, &0)).await?,
+        elements_are![eq("c")]
+    );
+    assert_that!(
+        titles(&db, array_cardinality("statuses", Comparison::Gt, &0)).await?,
+        elements_are![eq("a"), eq("b")]
+    );
+    assert_that!(
+        titles(&db, array_cardinality("tags", Comparison::Lt, &2)).await?,
+        elements_are![eq("b"), eq("c")]
+    );
+    assert_that!(
+        titles(&db, array_any("statuses", &Status::InProgress)).await?,
+        elements_are![eq("b")]
+    );
+    assert_that!(
+        titles(
+            &db,
+            array_contains(Article::RATINGS_COL, &vec![Rating::Good])
+        )
+        .await?,
+        elements_are![eq("a"), eq("b")]
+    );
+    assert_that!(
+        titles(&db, array_any(Article::TAGS_COL, &"rust")).await?,
+        elements_are![eq("a"), eq("b")]
+    );
+    assert_that!(
+        titles(&db, array_overlaps(Article::TAGS_COL, &["sql"])).await?,
+        elements_are![eq("a")]
+    );
+
+    Ok(())
+}
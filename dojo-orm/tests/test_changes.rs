@@ -0,0 +1,93 @@
+use chrono::{NaiveDateTime, Utc};
+use futures::StreamExt;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::{Model, UpdateModel};
+use dojo_orm::prelude::*;
+use dojo_orm::{Change, ChangeOp, Database, EmitChanges};
+
+mod common;
+
+#[tokio::test]
+async fn test_changes() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"], emit_changes)]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    #[derive(Debug, UpdateModel)]
+    struct UpdateUser {
+        name: Option<String>,
+    }
+
+    db.get()
+        .await?
+        .batch_execute(&User::changes_migration())
+        .await?;
+    let mut changes = db.changes::<User>().await?;
+
+    let now = Utc::now().naive_utc();
+    let user = User {
+        id: Uuid::new_v4(),
+        name: "linh1".to_string(),
+        email: "linh1@gmail.com".to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+    // compare with the stored row, Postgres keeps microseconds only
+    let user = db.insert(&[&user]).first_or_throw().await?;
+    let updated = db
+        .update::<User, UpdateUser>(&UpdateUser {
+            name: Some("linh2".to_string()),
+        })
+        .where_by(equals("id", &user.id))
+        .exec()
+        .await?;
+    db.delete::<User>()
+        .where_by(equals("id", &user.id))
+        .exec()
+        .await?;
+
+    assert_that!(
+        changes.next().await.unwrap()?,
+        eq(Change::Inserted(user.clone()))
+    );
+    assert_that!(
+        changes.next().await.unwrap()?,
+        eq(Change::Updated {
+            old: user,
+            new: updated.clone(),
+        })
+    );
+    assert_that!(changes.next().await.unwrap()?, eq(Change::Deleted(updated)));
+
+    // too large to notify, the insert still goes through with the key only
+    let user = User {
+        id: Uuid::new_v4(),
+        name: "linh".repeat(2000),
+        email: "linh3@gmail.com".to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+    db.insert(&[&user]).all().await?;
+    assert_that!(
+        changes.next().await.unwrap()?,
+        eq(Change::KeyOnly {
+            op: ChangeOp::Insert,
+            key: serde_json::json!({ "id": user.id }),
+        })
+    );
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use chrono::NaiveDateTime;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::{Model, Type};
+use dojo_orm::prelude::*;
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[dojo(name = "address")]
+struct Address {
+    street: String,
+    city: String,
+    zip: Option<i32>,
+}
+
+#[tokio::test]
+async fn test_composite() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "customers", sort_keys = ["created_at", "id"])]
+    struct Customer {
+        id: Uuid,
+        name: String,
+        address: Address,
+        previous_addresses: Vec<Address>,
+        created_at: NaiveDateTime,
+    }
+
+    let address = Address {
+        street: "1 Main St".to_string(),
+        city: "Hanoi".to_string(),
+        zip: None,
+    };
+    let previous = Address {
+        street: "2 Side St".to_string(),
+        city: "Hue".to_string(),
+        zip: Some(530000),
+    };
+
+    let customer = db
+        .insert(&[&Customer {
+            id: Uuid::new_v4(),
+            name: "linh".to_string(),
+            address: address.clone(),
+            previous_addresses: vec![previous.clone(), address.clone()],
+            created_at: chrono::Utc::now().naive_utc(),
+        }])
+        .first_or_throw()
+        .await?;
+    assert_that!(customer.address, eq(address.clone()));
+    assert_that!(
+        customer.previous_addresses,
+        elements_are![eq(previous.clone()), eq(address.clone())]
+    );
+
+    let customer = db
+        .bind::<Customer>()
+        // a bare `$1` compared with a composite is inferred as `record`
+        .where_by(raw(sql!("address = $1::address", &address)))
+        .first()
+        .await?;
+    assert_that!(
+        customer.map(|customer| customer.name),
+        some(eq("linh".to_string()))
+    );
+
+    let rows = db
+        .raw_query("SELECT (address).city AS city FROM customers")
+        .await?;
+    assert_that!(rows[0].get::<_, String>("city"), eq("Hanoi"));
+
+    Ok(())
+}
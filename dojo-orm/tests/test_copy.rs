@@ -0,0 +1,90 @@
+use chrono::{NaiveDateTime, SubsecRound, Utc};
+use futures::TryStreamExt;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::Database;
+
+mod common;
+
+#[tokio::test]
+async fn test_copy() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    // postgres keeps microseconds
+    let now = Utc::now().naive_utc().trunc_subsecs(6);
+    let users = (0..5000)
+        .map(|i| User {
+            id: Uuid::new_v4(),
+            name: format!("linh{}", i),
+            email: format!("linh{}@gmail.com", i),
+            created_at: now,
+            updated_at: now,
+        })
+        .collect::<Vec<_>>();
+
+    let written = db.copy_in(&users).await?;
+    assert_that!(written, eq(5000));
+    assert_that!(db.bind::<User>().count().await?, eq(5000));
+
+    let mut exported = db.copy_out::<User>().try_collect::<Vec<_>>().await?;
+    exported.sort_by_key(|user| user.name[4..].parse::<i32>().unwrap());
+    assert_that!(exported, eq(users));
+
+    // the stream is Send, so it can be consumed on another task
+    let db = db.clone();
+    let exported =
+        tokio::spawn(async move { db.copy_out::<User>().try_collect::<Vec<_>>().await }).await??;
+    assert_that!(exported, len(eq(5000)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_in_transaction() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let user = User {
+        id: Uuid::new_v4(),
+        name: "linh1".to_string(),
+        email: "linh1@gmail.com".to_string(),
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+    };
+
+    let tx = db.begin().await?;
+    assert_that!(tx.copy_in([&user]).await?, eq(1));
+    let users = tx.copy_out::<User>().try_collect::<Vec<_>>().await?;
+    assert_that!(users.len(), eq(1));
+    assert_that!(db.bind::<User>().count().await?, eq(0));
+
+    tx.rollback().await?;
+    assert_that!(db.bind::<User>().count().await?, eq(0));
+
+    Ok(())
+}
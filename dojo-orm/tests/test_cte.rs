@@ -0,0 +1,101 @@
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::order_by::asc;
+use dojo_orm::predicates::{equals, in_list};
+use dojo_orm::{sql, Database};
+
+mod common;
+
+#[tokio::test]
+async fn test_cte() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "categories", sort_keys = ["id"])]
+    struct Category {
+        id: i32,
+        parent_id: Option<i32>,
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "tree", sort_keys = ["id"])]
+    struct Node {
+        id: i32,
+        parent_id: Option<i32>,
+        name: String,
+        depth: i32,
+    }
+
+    // books -> fiction -> fantasy, books -> science; music is another root
+    let categories = [
+        (1, None, "books"),
+        (2, Some(1), "fiction"),
+        (3, Some(2), "fantasy"),
+        (4, Some(1), "science"),
+        (5, None, "music"),
+    ]
+    .into_iter()
+    .map(|(id, parent_id, name)| Category {
+        id,
+        parent_id,
+        name: name.to_string(),
+    })
+    .collect::<Vec<_>>();
+    db.insert(&categories.iter().collect::<Vec<_>>())
+        .all()
+        .await?;
+
+    let nodes = db
+        .bind::<Node>()
+        .with_recursive(
+            "tree",
+            db.bind::<Category>()
+                .where_by(equals("name", &"books"))
+                .subquery(&["id", "parent_id", "name", "0 AS depth"])?,
+            sql!("SELECT c.id, c.parent_id, c.name, t.depth + 1 FROM categories c JOIN tree t ON c.parent_id = t.id"),
+        )
+        .where_by(in_list("depth", &vec![1, 2]))
+        .order_by(asc("id"))
+        .all()
+        .await?;
+    assert_that!(
+        nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.depth))
+            .collect::<Vec<_>>(),
+        elements_are![eq(("fiction", 1)), eq(("fantasy", 2)), eq(("science", 1))]
+    );
+
+    // the cte's params come before the outer select's
+    let count = db
+        .bind::<Node>()
+        .with(
+            "tree",
+            db.bind::<Category>()
+                .where_by(equals("parent_id", &1))
+                .subquery(&["id", "parent_id", "name", "1 AS depth"])?,
+        )
+        .where_by(equals("name", &"science"))
+        .count()
+        .await?;
+    assert_that!(count, eq(1));
+
+    let page = db
+        .bind::<Node>()
+        .with(
+            "tree",
+            db.bind::<Category>()
+                .subquery(&["id", "parent_id", "name", "0 AS depth"])?,
+        )
+        .cursor(Some(2), None, None, None)
+        .await?;
+    assert_that!(page.items.len(), eq(2));
+    assert_that!(page.total_nodes, some(eq(5)));
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use async_graphql::connection::CursorType;
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::pagination::{Cursor, CursorCodec, CursorError, Row};
+use dojo_orm::{Database, Value};
+
+mod common;
+
+#[tokio::test]
+async fn test_signed_cursor() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    for name in ["linh1", "linh2", "linh3"] {
+        db.insert(&[&User {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            email: format!("{}@gmail.com", name),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        }])
+        .all()
+        .await?;
+    }
+
+    let db = db.clone().with_cursor_codec(
+        CursorCodec::new()
+            .with_signing_key("secret")
+            .with_encryption_key([1; 32]),
+    );
+
+    // the keys belong to this database only, not to other databases or to
+    // the unkeyed `Cursor::decode`
+    let other = db
+        .clone()
+        .with_cursor_codec(CursorCodec::new().with_signing_key("other"));
+
+    let pagination = db.bind::<User>().cursor(Some(1), None, None, None).await?;
+    let encoded = pagination.encode_cursor(&pagination.end_cursor().unwrap())?;
+    assert_that!(Cursor::decode(&encoded), err(eq(CursorError::MissingKey)));
+    assert_that!(other.decode_cursor(&encoded).is_err(), eq(true));
+
+    let after = db.decode_cursor(&encoded)?;
+    let pagination = db
+        .bind::<User>()
+        .cursor(Some(1), Some(after), None, None)
+        .await?;
+    assert_that!(
+        pagination.items,
+        elements_are![pat!(User {
+            id: anything(),
+            name: eq("linh2"),
+            email: eq("linh2@gmail.com"),
+            created_at: anything(),
+            updated_at: anything(),
+        })]
+    );
+
+    let mut tampered = encoded.clone();
+    let ch = if &tampered[5..6] == "A" { "B" } else { "A" };
+    tampered.replace_range(5..6, ch);
+    let err = db.decode_cursor(&tampered).unwrap_err();
+    assert_that!(
+        err.downcast_ref::<CursorError>(),
+        some(eq(&CursorError::InvalidSignature))
+    );
+
+    // the async-graphql scalar decodes without the key
+    let unsigned = db
+        .clone()
+        .with_cursor_codec(CursorCodec::new())
+        .bind::<User>()
+        .cursor(Some(1), None, None, None)
+        .await?
+        .end_cursor()
+        .unwrap()
+        .encode()?;
+    let after = <Cursor as CursorType>::decode_cursor(&unsigned)?;
+    let err = db
+        .bind::<User>()
+        .cursor(Some(1), Some(after), None, None)
+        .await
+        .unwrap_err();
+    assert_that!(
+        err.downcast_ref::<CursorError>(),
+        some(eq(&CursorError::MissingSignature))
+    );
+
+    let forged = Cursor::new(vec![Row::new(
+        "password".to_string(),
+        Value::String("x".to_string()),
+    )]);
+    let err = db
+        .bind::<User>()
+        .cursor(Some(1), Some(forged), None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<CursorError>(),
+        Some(CursorError::MismatchedColumns { .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::order_by::{asc, desc};
+use dojo_orm::predicates::equals;
+use dojo_orm::Database;
+
+mod common;
+
+#[tokio::test]
+async fn test_distinct() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["name"])]
+    struct UserName {
+        name: String,
+    }
+
+    // three signups of linh1 and two of linh2, the last one of each is the latest
+    let now = Utc::now().naive_utc();
+    let users = [
+        ("linh1", 1),
+        ("linh2", 2),
+        ("linh1", 3),
+        ("linh2", 4),
+        ("linh1", 5),
+    ]
+    .iter()
+    .map(|(name, i)| User {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        email: format!("{}_{}@gmail.com", name, i),
+        created_at: now + Duration::seconds(*i),
+        updated_at: now,
+    })
+    .collect::<Vec<_>>();
+    db.insert(&users.iter().collect::<Vec<_>>()).all().await?;
+
+    let mut latest = db.bind::<User>();
+    let latest = latest
+        .distinct_on(&["name"])
+        .order_by(asc("name"))
+        .order_by(desc("created_at"));
+    let found = latest.all().await?;
+    assert_that!(
+        found
+            .iter()
+            .map(|user| user.email.as_str())
+            .collect::<Vec<_>>(),
+        elements_are![eq("linh1_5@gmail.com"), eq("linh2_4@gmail.com")]
+    );
+    assert_that!(latest.count().await?, eq(2));
+
+    let names = db
+        .bind::<UserName>()
+        .distinct()
+        .order_by(asc("name"))
+        .all()
+        .await?;
+    assert_that!(names.len(), eq(2));
+
+    let count = db
+        .bind::<UserName>()
+        .distinct()
+        .where_by(equals("name", &"linh2"))
+        .count()
+        .await?;
+    assert_that!(count, eq(1));
+    assert_that!(db.bind::<User>().count().await?, eq(5));
+
+    // created_at can't come before name in the order by
+    let result = db
+        .bind::<User>()
+        .distinct_on(&["name"])
+        .order_by(desc("created_at"))
+        .all()
+        .await;
+    assert_that!(result.is_err(), eq(true));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct_on_cursor() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let now = Utc::now().naive_utc();
+    let users = [
+        ("linh1", 1),
+        ("linh2", 2),
+        ("linh1", 3),
+        ("linh3", 4),
+        ("linh2", 5),
+        ("linh3", 6),
+    ]
+    .iter()
+    .map(|(name, i)| User {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        email: format!("{}_{}@gmail.com", name, i),
+        created_at: now + Duration::seconds(*i),
+        updated_at: now,
+    })
+    .collect::<Vec<_>>();
+    db.insert(&users.iter().collect::<Vec<_>>()).all().await?;
+
+    let mut latest = db.bind::<User>();
+    let latest = latest
+        .distinct_on(&["name"])
+        .order_by(asc("name"))
+        .order_by(desc("created_at"));
+
+    // every group shows up once, with its latest row, across the pages
+    let mut emails = vec![];
+    let mut after = None;
+    loop {
+        let page = latest.cursor(Some(1), after, None, None).await?;
+        assert_that!(page.total_nodes, some(eq(3)));
+        emails.extend(page.items.iter().map(|user| user.email.clone()));
+        if !page.has_next {
+            break;
+        }
+        after = page.end_cursor();
+    }
+    assert_that!(
+        emails,
+        elements_are![
+            eq("linh1_3@gmail.com"),
+            eq("linh2_5@gmail.com"),
+            eq("linh3_6@gmail.com")
+        ]
+    );
+
+    // paging backward keeps the latest row of each group too
+    let page = latest.cursor(None, None, Some(2), None).await?;
+    assert_that!(
+        page.items
+            .iter()
+            .map(|user| user.email.as_str())
+            .collect::<Vec<_>>(),
+        elements_are![eq("linh2_5@gmail.com"), eq("linh3_6@gmail.com")]
+    );
+    let page = latest
+        .cursor(None, None, Some(2), page.start_cursor())
+        .await?;
+    assert_that!(
+        page.items
+            .iter()
+            .map(|user| user.email.as_str())
+            .collect::<Vec<_>>(),
+        elements_are![eq("linh1_3@gmail.com")]
+    );
+
+    // the rows of the groups come from a cte, a lock on them would lock nothing
+    let result = db
+        .bind::<User>()
+        .distinct_on(&["name"])
+        .order_by(asc("name"))
+        .for_update()
+        .cursor(Some(1), None, None, None)
+        .await;
+    assert_that!(result.is_err(), eq(true));
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::{Model, Type};
+use dojo_orm::prelude::*;
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+#[dojo(name = "ticket_status", rename_all = "snake_case")]
+enum Status {
+    Open,
+    InProgress,
+    Closed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+#[dojo(storage = "text", rename_all = "lowercase")]
+enum Kind {
+    Bug,
+    #[dojo(rename = "feature-request")]
+    FeatureRequest,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+#[dojo(storage = "int", repr = i16)]
+enum Priority {
+    #[dojo(value = 1)]
+    Low,
+    #[dojo(value = 5)]
+    Medium,
+    High = 9,
+}
+
+#[tokio::test]
+async fn test_enum_storage() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "tickets", sort_keys = ["priority", "id"])]
+    struct Ticket {
+        id: Uuid,
+        title: String,
+        status: Status,
+        kind: Kind,
+        priority: Priority,
+        created_at: NaiveDateTime,
+    }
+
+    let tickets = [
+        ("crash", Status::Open, Kind::Bug, Priority::High),
+        (
+            "dark mode",
+            Status::InProgress,
+            Kind::FeatureRequest,
+            Priority::Low,
+        ),
+        ("typo", Status::Closed, Kind::Bug, Priority::Medium),
+    ]
+    .into_iter()
+    .map(|(title, status, kind, priority)| Ticket {
+        id: Uuid::new_v4(),
+        title: title.to_string(),
+        status,
+        kind,
+        priority,
+        created_at: Utc::now().naive_utc(),
+    })
+    .collect::<Vec<_>>();
+    db.insert(&tickets.iter().collect::<Vec<_>>()).all().await?;
+
+    let rows = db
+        .raw_query("SELECT title, status::text, kind, priority FROM tickets ORDER BY title")
+        .await?;
+    assert_that!(rows[0].get::<_, String>("status"), eq("open"));
+    assert_that!(rows[0].get::<_, String>("kind"), eq("bug"));
+    assert_that!(rows[0].get::<_, i16>("priority"), eq(9));
+    assert_that!(rows[1].get::<_, String>("status"), eq("in_progress"));
+    assert_that!(rows[1].get::<_, String>("kind"), eq("feature-request"));
+
+    let tickets = db
+        .bind::<Ticket>()
+        .where_by(and(&[
+            equals("kind", &Kind::Bug),
+            equals("status", &Status::Closed),
+        ]))
+        .all()
+        .await?;
+    assert_that!(
+        tickets
+            .iter()
+            .map(|t| (t.title.as_str(), t.priority))
+            .collect::<Vec<_>>(),
+        elements_are![eq(("typo", Priority::Medium))]
+    );
+
+    let pagination = db
+        .bind::<Ticket>()
+        .cursor(Some(2), None, None, None)
+        .await?;
+    assert_that!(
+        pagination
+            .items
+            .iter()
+            .map(|t| t.priority)
+            .collect::<Vec<_>>(),
+        elements_are![eq(Priority::Low), eq(Priority::Medium)]
+    );
+
+    let after = pagination.end_cursor();
+    let pagination = db
+        .bind::<Ticket>()
+        .cursor(Some(2), after, None, None)
+        .await?;
+    assert_that!(
+        pagination
+            .items
+            .iter()
+            .map(|t| (t.title.as_str(), t.status, t.kind))
+            .collect::<Vec<_>>(),
+        elements_are![eq(("crash", Status::Open, Kind::Bug))]
+    );
+    assert_that!(pagination.has_next, eq(false));
+
+    Ok(())
+}
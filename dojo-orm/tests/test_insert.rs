@@ -215,6 +215,72 @@ async fn test_insert_many() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_insert_many_batches() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let user = |i: usize| User {
+        id: Uuid::new_v4(),
+        name: format!("linh{}", i),
+        email: format!("linh{}@gmail.com", i),
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+    };
+
+    // 5 columns, so more than 13107 rows take several statements
+    let inputs = (0..30_000).map(user).collect::<Vec<_>>();
+    let refs = inputs.iter().collect::<Vec<_>>();
+    let users = db.insert(&refs).all().await?;
+    assert_that!(users.len(), eq(30_000));
+    assert!(users
+        .iter()
+        .zip(&inputs)
+        .all(|(user, input)| user.id == input.id));
+
+    let users = db
+        .insert(&refs)
+        .on_conflict(&["email"])
+        .do_update(&[("name", &"linh")])
+        .all()
+        .await?;
+    assert_that!(users.len(), eq(30_000));
+    assert!(users.iter().all(|user| user.name == "linh"));
+
+    // a failing batch rolls back the ones before it
+    let mut inputs = (30_000..50_000).map(user).collect::<Vec<_>>();
+    inputs[19_999].email = "linh0@gmail.com".to_string();
+    let refs = inputs.iter().collect::<Vec<_>>();
+    assert!(db.insert(&refs).all().await.is_err());
+    assert_that!(db.bind::<User>().count().await?, eq(30_000));
+
+    // first inserts every row too, batched the same way
+    let inputs = (50_000..70_000).map(user).collect::<Vec<_>>();
+    let refs = inputs.iter().collect::<Vec<_>>();
+    let first = db.insert(&refs).first_or_throw().await?;
+    assert_that!(first.id, eq(inputs[0].id));
+    let first = db
+        .insert(&refs)
+        .on_conflict(&["email"])
+        .do_nothing()
+        .first()
+        .await?;
+    assert_that!(first.is_none(), eq(true));
+    assert_that!(db.bind::<User>().count().await?, eq(50_000));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_insert_many_empty() -> anyhow::Result<()> {
     let db: Database;
@@ -0,0 +1,153 @@
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::predicates::{equals, in_list};
+use dojo_orm::Database;
+
+mod common;
+
+macro_rules! create_users {
+    ($db: ident, names = $($name:literal),+) => {
+        $db.insert(&[
+            $(&User {
+                id: Uuid::new_v4(),
+                name: $name.to_string(),
+                email: concat!($name, "@gmail.com").to_string(),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+            }),+
+        ]).all().await?;
+    };
+}
+
+#[tokio::test]
+async fn test_insert_from() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "archived_users", sort_keys = ["created_at", "id"])]
+    struct ArchivedUser {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        archived_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3");
+
+    let names = vec!["linh1".to_string(), "linh2".to_string()];
+    let archived = db
+        .insert_from::<ArchivedUser>(db.bind::<User>().where_by(in_list("name", &names)))
+        .columns(&[
+            ("id", "id"),
+            ("name", "name"),
+            ("email", "email"),
+            ("created_at", "created_at"),
+        ])
+        .all()
+        .await?;
+    assert_that!(archived.len(), eq(2));
+    assert_that!(
+        archived
+            .iter()
+            .map(|user| user.name.as_str())
+            .collect::<Vec<_>>(),
+        unordered_elements_are![eq("linh1"), eq("linh2")]
+    );
+    assert_that!(db.bind::<ArchivedUser>().count().await?, eq(2));
+
+    // archiving linh1 again only refreshes its name
+    let archived = db
+        .insert_from::<ArchivedUser>(db.bind::<User>().where_by(equals("name", &"linh1")))
+        .columns(&[
+            ("id", "id"),
+            ("name", "upper(name)"),
+            ("email", "email"),
+            ("created_at", "created_at"),
+        ])
+        .on_conflict(&["id"])
+        .do_update(&[("name", &"LINH1")])
+        .all()
+        .await?;
+    assert_that!(archived.len(), eq(1));
+    assert_that!(archived[0].name, eq("LINH1"));
+
+    let archived = db
+        .insert_from::<ArchivedUser>(db.bind::<User>().where_by(equals("name", &"linh2")))
+        .columns(&[
+            ("id", "id"),
+            ("name", "name"),
+            ("email", "email"),
+            ("created_at", "created_at"),
+            ("archived_at", "updated_at"),
+        ])
+        .on_conflict(&["id"])
+        .do_nothing()
+        .first()
+        .await?;
+    assert_that!(archived, none());
+    assert_that!(db.bind::<ArchivedUser>().count().await?, eq(2));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_from_in_transaction() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1");
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["id"])]
+    struct UserCopy {
+        id: Uuid,
+        name: String,
+        email: String,
+    }
+
+    let tx = db.begin().await?;
+    let copied = tx
+        .insert_from::<UserCopy>(tx.bind::<User>().where_by(equals("name", &"linh1")))
+        .columns(&[
+            ("id", "gen_random_uuid()"),
+            ("name", "name || '_copy'"),
+            ("email", "'copy_' || email"),
+        ])
+        .all()
+        .await?;
+    assert_that!(copied.len(), eq(1));
+    assert_that!(copied[0].name, eq("linh1_copy"));
+    assert_that!(db.bind::<User>().count().await?, eq(1));
+    tx.commit().await?;
+
+    assert_that!(db.bind::<User>().count().await?, eq(2));
+
+    Ok(())
+}
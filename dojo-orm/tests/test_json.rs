@@ -0,0 +1,141 @@
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::{EmbeddedModel, Model};
+use dojo_orm::prelude::*;
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, Clone, EmbeddedModel)]
+struct ProductDetail {
+    color: String,
+    size: Option<ProductSize>,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ProductSize {
+    width: i32,
+}
+
+#[tokio::test]
+async fn test_json() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "products", sort_keys = ["created_at", "id"])]
+    struct Product {
+        id: Uuid,
+        name: String,
+        detail: Option<ProductDetail>,
+        price: Option<i32>,
+        created_at: NaiveDateTime,
+    }
+
+    async fn names(db: &Database, predicate: WherePredicate<'_>) -> anyhow::Result<Vec<String>> {
+        let products = db
+            .bind::<Product>()
+            .where_by(predicate)
+            .order_by(asc("name"))
+            .all()
+            .await?;
+
+        Ok(products.into_iter().map(|product| product.name).collect())
+    }
+
+    let products = [
+        ("a", "red", Some(30), vec!["new"]),
+        ("b", "blue", Some(10), vec![]),
+        ("c", "red", None, vec!["sale"]),
+    ]
+    .into_iter()
+    .map(|(name, color, width, tags)| Product {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        detail: Some(ProductDetail {
+            color: color.to_string(),
+            size: width.map(|width| ProductSize { width }),
+            tags: tags.into_iter().map(|tag| tag.to_string()).collect(),
+        }),
+        price: None,
+        created_at: Utc::now().naive_utc(),
+    })
+    .collect::<Vec<_>>();
+    db.insert(&products.iter().collect::<Vec<_>>())
+        .all()
+        .await?;
+
+    let color = json_field("detail", "color");
+    assert_that!(
+        names(&db, equals(&color, &"red")).await?,
+        elements_are![eq("a"), eq("c")]
+    );
+
+    let width = json_path("detail", &["size", "width"]);
+    assert_that!(
+        names(&db, equals(&width, &"10")).await?,
+        elements_are![eq("b")]
+    );
+
+    let value = serde_json::json!({ "color": "red", "tags": ["sale"] });
+    assert_that!(
+        names(&db, json_contains("detail", &value)).await?,
+        elements_are![eq("c")]
+    );
+
+    assert_that!(
+        names(&db, json_has_key("detail", "tags")).await?,
+        elements_are![eq("a"), eq("b"), eq("c")]
+    );
+    let keys = vec!["missing", "color"];
+    assert_that!(
+        names(&db, json_has_any_key("detail", &keys)).await?,
+        elements_are![eq("a"), eq("b"), eq("c")]
+    );
+    assert_that!(
+        names(&db, json_has_all_keys("detail", &keys)).await?,
+        empty()
+    );
+
+    assert_that!(
+        names(&db, json_path_exists("detail", "$.size ? (@.width > 20)")).await?,
+        elements_are![eq("a")]
+    );
+
+    let products = db
+        .bind::<Product>()
+        .where_by(json_has_key("detail", "size"))
+        .order_by(asc(&width))
+        .all()
+        .await?;
+    assert_that!(
+        products
+            .iter()
+            .map(|product| product.name.as_str())
+            .collect::<Vec<_>>(),
+        elements_are![eq("b"), eq("a"), eq("c")]
+    );
+
+    // their cursors couldn't hold a json value to continue from
+    for order_by in [asc(&width), asc("detail")] {
+        let result = db
+            .bind::<Product>()
+            .order_by(order_by)
+            .cursor(Some(1), None, None, None)
+            .await;
+        assert_that!(result.is_err(), eq(true));
+    }
+    let page = db
+        .bind::<Product>()
+        .order_by(asc("price"))
+        .cursor(Some(1), None, None, None)
+        .await?;
+    assert_that!(page.end_cursor().unwrap().values.len(), eq(3));
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use common::*;
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Event {
+    id: i32,
+}
+
+#[tokio::test]
+async fn test_listen_and_notify() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    let mut notifications = db.listen(&["events", "other"]).await?;
+    db.notify("events", "hello").await?;
+    db.notify("other", "world").await?;
+
+    let notification = notifications.next().await.unwrap()?;
+    assert_that!(notification.channel(), eq("events"));
+    assert_that!(notification.payload(), eq("hello"));
+    let notification = notifications.next().await.unwrap()?;
+    assert_that!(notification.channel(), eq("other"));
+    assert_that!(notification.payload(), eq("world"));
+
+    let mut events = db.listen_json::<Event>(&["events"]).await?;
+    db.notify("events", "not json").await?;
+    db.notify_json("events", &Event { id: 1 }).await?;
+    assert_that!(events.next().await.unwrap().is_err(), eq(true));
+    let event = events.next().await.unwrap()?;
+    assert_that!(event.channel, eq("events"));
+    assert_that!(event.payload, eq(Event { id: 1 }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_listen_reconnects() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    let mut notifications = db.listen(&["events"]).await?;
+    let terminated = db
+        .raw_query(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+             WHERE datname = current_database() AND query LIKE 'LISTEN%'",
+        )
+        .await?;
+    assert_that!(terminated.len(), eq(1));
+
+    // keep notifying until the new connection is listening again
+    let received = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            db.notify("events", "hello").await?;
+            let next = tokio::time::timeout(Duration::from_millis(200), notifications.next());
+            if let Ok(Some(Ok(notification))) = next.await {
+                return anyhow::Ok(notification);
+            }
+        }
+    })
+    .await??;
+    assert_that!(received.payload(), eq("hello"));
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use chrono::{NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::prelude::*;
+use dojo_orm::Database;
+
+mod common;
+
+macro_rules! create_users {
+    ($db: ident, names = $($name:literal),+) => {
+        $db.insert(&[
+            $(&User {
+                id: Uuid::new_v4(),
+                name: $name.to_string(),
+                email: concat!($name, "@gmail.com").to_string(),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+            }),+
+        ]).all().await?;
+    };
+}
+
+#[tokio::test]
+async fn test_skip_locked_workers() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    fn names(users: Vec<User>) -> Vec<String> {
+        users.into_iter().map(|user| user.name).collect()
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3", "linh4");
+
+    let worker1 = db.begin().await?;
+    let claimed1 = worker1
+        .bind::<User>()
+        .order_by(asc("name"))
+        .limit(2)
+        .for_update()
+        .skip_locked()
+        .all()
+        .await?;
+
+    let worker2 = db.begin().await?;
+    let claimed2 = worker2
+        .bind::<User>()
+        .order_by(asc("name"))
+        .limit(2)
+        .for_update()
+        .skip_locked()
+        .all()
+        .await?;
+
+    assert_that!(names(claimed1), elements_are![eq("linh1"), eq("linh2")]);
+    assert_that!(names(claimed2), elements_are![eq("linh3"), eq("linh4")]);
+
+    // every row is locked now, a third worker can't wait for any of them
+    let worker3 = db.begin().await?;
+    let result = worker3
+        .bind::<User>()
+        .where_by(equals("name", &"linh1"))
+        .for_update()
+        .nowait()
+        .all()
+        .await;
+    assert_that!(result.is_err(), eq(true));
+    worker3.rollback().await?;
+
+    worker1.commit().await?;
+    let worker3 = db.begin().await?;
+    let claimed3 = worker3
+        .bind::<User>()
+        .order_by(asc("name"))
+        .for_share()
+        .skip_locked()
+        .all()
+        .await?;
+    assert_that!(names(claimed3), elements_are![eq("linh1"), eq("linh2")]);
+
+    worker2.commit().await?;
+    worker3.commit().await?;
+
+    Ok(())
+}
@@ -5,6 +5,8 @@ use uuid::Uuid;
 
 use common::*;
 use dojo_macros::Model;
+use dojo_orm::pagination::{CursorOptions, TotalCount};
+use dojo_orm::prelude::*;
 use dojo_orm::Database;
 
 mod common;
@@ -82,7 +84,7 @@ async fn test_paging_forward() -> anyhow::Result<()> {
         })]
     );
     assert_that!(pagination.has_next, eq(true));
-    assert_that!(pagination.has_previous, eq(false));
+    assert_that!(pagination.has_previous, eq(true));
 
     let cursor = pagination.end_cursor().unwrap();
     let (first, after) = create_paging_args!(first = 1, after = cursor);
@@ -98,14 +100,14 @@ async fn test_paging_forward() -> anyhow::Result<()> {
         })]
     );
     assert_that!(pagination.has_next, eq(false));
-    assert_that!(pagination.has_previous, eq(false));
+    assert_that!(pagination.has_previous, eq(true));
 
     let cursor = pagination.end_cursor().unwrap();
     let (first, after) = create_paging_args!(first = 1, after = cursor);
     let pagination = db.bind::<User>().cursor(first, after, None, None).await?;
     assert_that!(pagination.items, empty());
     assert_that!(pagination.has_next, eq(false));
-    assert_that!(pagination.has_previous, eq(false));
+    assert_that!(pagination.has_previous, eq(true));
 
     Ok(())
 }
@@ -142,7 +144,7 @@ async fn test_paging_backward() -> anyhow::Result<()> {
     assert_that!(pagination.has_next, eq(false));
     assert_that!(pagination.has_previous, eq(true));
 
-    let cursor = pagination.end_cursor().unwrap();
+    let cursor = pagination.start_cursor().unwrap();
     let (last, before) = create_paging_args!(last = 1, before = cursor);
     let pagination = db.bind::<User>().cursor(None, None, last, before).await?;
     assert_that!(
@@ -155,10 +157,10 @@ async fn test_paging_backward() -> anyhow::Result<()> {
             updated_at: anything(),
         })]
     );
-    assert_that!(pagination.has_next, eq(false));
+    assert_that!(pagination.has_next, eq(true));
     assert_that!(pagination.has_previous, eq(true));
 
-    let cursor = pagination.end_cursor().unwrap();
+    let cursor = pagination.start_cursor().unwrap();
     let (last, before) = create_paging_args!(last = 1, before = cursor);
     let pagination = db.bind::<User>().cursor(None, None, last, before).await?;
     assert_that!(
@@ -171,15 +173,300 @@ async fn test_paging_backward() -> anyhow::Result<()> {
             updated_at: anything(),
         })]
     );
-    assert_that!(pagination.has_next, eq(false));
+    assert_that!(pagination.has_next, eq(true));
     assert_that!(pagination.has_previous, eq(false));
 
-    let cursor = pagination.end_cursor().unwrap();
+    let cursor = pagination.start_cursor().unwrap();
     let (last, before) = create_paging_args!(last = 1, before = cursor);
     let pagination = db.bind::<User>().cursor(None, None, last, before).await?;
     assert_that!(pagination.items, empty());
+    assert_that!(pagination.has_next, eq(true));
+    assert_that!(pagination.has_previous, eq(false));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_paging_offset() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3", "linh4", "linh5");
+
+    let pagination = db
+        .bind::<User>()
+        .where_by(in_list("name", &vec!["linh1", "linh2", "linh3", "linh5"]))
+        .order_by(desc("name"))
+        .paginate(2, 3)
+        .await?;
+    assert_that!(
+        pagination.items,
+        elements_are![pat!(User {
+            id: anything(),
+            name: eq("linh1"),
+            email: eq("linh1@gmail.com"),
+            created_at: anything(),
+            updated_at: anything(),
+        })]
+    );
+    assert_that!(pagination.page, eq(2));
+    assert_that!(pagination.per_page, eq(3));
+    assert_that!(pagination.total_nodes, eq(4));
+    assert_that!(pagination.total_pages, eq(2));
+    assert_that!(pagination.has_next(), eq(false));
+    assert_that!(pagination.has_previous(), eq(true));
+
+    let pagination = db.bind::<User>().paginate(1, 2).await?;
+    assert_that!(
+        pagination.items,
+        elements_are![
+            pat!(User {
+                id: anything(),
+                name: eq("linh1"),
+                email: eq("linh1@gmail.com"),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+            pat!(User {
+                id: anything(),
+                name: eq("linh2"),
+                email: eq("linh2@gmail.com"),
+                created_at: anything(),
+                updated_at: anything(),
+            })
+        ]
+    );
+    assert_that!(pagination.total_pages, eq(3));
+
+    assert!(db.bind::<User>().paginate(0, 2).await.is_err());
+    assert!(db.bind::<User>().paginate(1, 0).await.is_err());
+    assert!(db.bind::<User>().paginate(i64::MAX, 2).await.is_err());
+    assert!(db.bind::<User>().limit(1).paginate(1, 2).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_paging_with_order_by() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3");
+
+    let pagination = db
+        .bind::<User>()
+        .order_by(desc("name"))
+        .cursor(Some(2), None, None, None)
+        .await?;
+    assert_that!(
+        pagination.items,
+        elements_are![
+            pat!(User {
+                id: anything(),
+                name: eq("linh3"),
+                email: eq("linh3@gmail.com"),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+            pat!(User {
+                id: anything(),
+                name: eq("linh2"),
+                email: eq("linh2@gmail.com"),
+                created_at: anything(),
+                updated_at: anything(),
+            })
+        ]
+    );
+    assert_that!(pagination.has_next, eq(true));
+
+    let cursor = pagination.end_cursor().unwrap();
+    let pagination = db
+        .bind::<User>()
+        .order_by(desc("name"))
+        .cursor(Some(2), Some(cursor), None, None)
+        .await?;
+    assert_that!(
+        pagination.items,
+        elements_are![pat!(User {
+            id: anything(),
+            name: eq("linh1"),
+            email: eq("linh1@gmail.com"),
+            created_at: anything(),
+            updated_at: anything(),
+        })]
+    );
     assert_that!(pagination.has_next, eq(false));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_paging_with_nullable_key() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "products", sort_keys = ["id"])]
+    struct Product {
+        id: Uuid,
+        name: String,
+        price: Option<i32>,
+        created_at: NaiveDateTime,
+    }
+
+    let products = [Some(200), None, Some(100), None]
+        .into_iter()
+        .enumerate()
+        .map(|(i, price)| Product {
+            id: Uuid::new_v4(),
+            name: format!("product{}", i),
+            price,
+            created_at: Utc::now().naive_utc(),
+        })
+        .collect::<Vec<_>>();
+    let inputs = products.iter().collect::<Vec<_>>();
+    db.insert(&inputs).all().await?;
+
+    let mut prices = vec![];
+    let mut after = None;
+    loop {
+        let pagination = db
+            .bind::<Product>()
+            .order_by(asc("price"))
+            .cursor(Some(1), after, None, None)
+            .await?;
+        prices.extend(pagination.items.iter().map(|p| p.price));
+        if !pagination.has_next {
+            break;
+        }
+        after = pagination.end_cursor();
+    }
+    assert_that!(
+        prices,
+        elements_are![eq(Some(100)), eq(Some(200)), eq(None), eq(None)]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_paging_backward_keeps_natural_order() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3", "linh4");
+
+    let (last, before) = create_paging_args!(last = 2);
+    let pagination = db.bind::<User>().cursor(None, None, last, before).await?;
+    let names = pagination
+        .items
+        .iter()
+        .map(|user| user.name.as_str())
+        .collect::<Vec<_>>();
+    assert_that!(names, elements_are![eq("linh3"), eq("linh4")]);
+    assert_that!(pagination.has_next, eq(false));
+    assert_that!(pagination.has_previous, eq(true));
+
+    let cursor = pagination.start_cursor().unwrap();
+    let (last, before) = create_paging_args!(last = 2, before = cursor);
+    let pagination = db.bind::<User>().cursor(None, None, last, before).await?;
+    let names = pagination
+        .items
+        .iter()
+        .map(|user| user.name.as_str())
+        .collect::<Vec<_>>();
+    assert_that!(names, elements_are![eq("linh1"), eq("linh2")]);
+    assert_that!(pagination.has_next, eq(true));
     assert_that!(pagination.has_previous, eq(false));
 
+    let cursor = pagination.end_cursor().unwrap();
+    let pagination = db
+        .bind::<User>()
+        .cursor(Some(10), Some(cursor), None, None)
+        .await?;
+    let names = pagination
+        .items
+        .iter()
+        .map(|user| user.name.as_str())
+        .collect::<Vec<_>>();
+    assert_that!(names, elements_are![eq("linh3"), eq("linh4")]);
+    assert_that!(pagination.has_next, eq(false));
+    assert_that!(pagination.has_previous, eq(true));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_paging_total_count() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3");
+
+    let pagination = db.bind::<User>().cursor(Some(1), None, None, None).await?;
+    assert_that!(pagination.total_nodes, some(eq(3)));
+
+    let options = CursorOptions::default().total_count(TotalCount::None);
+    let pagination = db
+        .bind::<User>()
+        .cursor_with_options(Some(1), None, None, None, options)
+        .await?;
+    assert_that!(pagination.items.len(), eq(1));
+    assert_that!(pagination.total_nodes, none());
+
+    let options = CursorOptions::default().total_count(TotalCount::Estimated);
+    let pagination = db
+        .bind::<User>()
+        .cursor_with_options(Some(1), None, None, None, options)
+        .await?;
+    assert_that!(pagination.total_nodes, some(ge(0)));
+
+    let pagination = db
+        .bind::<User>()
+        .where_by(equals("name", &"linh1"))
+        .cursor_with_options(Some(1), None, None, None, options)
+        .await?;
+    assert_that!(pagination.total_nodes, some(ge(0)));
+
     Ok(())
 }
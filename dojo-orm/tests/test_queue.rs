@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use common::*;
+use dojo_orm::queue::{Job, JobStatus, LostClaim, Queue, QueueOptions};
+use dojo_orm::Database;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Email {
+    to: String,
+}
+
+#[tokio::test]
+async fn test_queue_retry_and_dead_letter() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+    Queue::migrate(&db).await?;
+
+    let queue = Queue::new(&db, "emails").with_options(
+        QueueOptions::default()
+            .max_attempts(2)
+            .backoff(Duration::ZERO, Duration::ZERO),
+    );
+    queue
+        .enqueue(&Email {
+            to: "linh1@gmail.com".to_string(),
+        })
+        .await?;
+    queue
+        .enqueue(&Email {
+            to: "linh2@gmail.com".to_string(),
+        })
+        .await?;
+
+    let job1 = queue.fetch::<Email>().await?.unwrap();
+    let job2 = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(job1.payload.to, eq("linh1@gmail.com"));
+    assert_that!(job1.attempts, eq(1));
+    assert_that!(job2.payload.to, eq("linh2@gmail.com"));
+    assert_that!(queue.fetch::<Email>().await?.is_none(), eq(true));
+
+    queue.complete(&job1).await?;
+    queue.fail(&job2, "smtp down").await?;
+
+    let job2 = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(job2.attempts, eq(2));
+    assert_that!(job2.last_error, some(eq("smtp down")));
+    queue.fail(&job2, "smtp still down").await?;
+    assert_that!(queue.fetch::<Email>().await?.is_none(), eq(true));
+
+    let dead = queue.dead_letters().await?;
+    assert_that!(dead.len(), eq(1));
+    assert_that!(dead[0].payload["to"], eq("linh2@gmail.com"));
+    assert_that!(dead[0].status, eq(JobStatus::Dead));
+    assert_that!(dead[0].last_error, some(eq("smtp still down")));
+
+    queue.retry_dead(&dead[0]).await?;
+    let job2 = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(job2.attempts, eq(1));
+
+    // the job is running again, retrying it would reset the claim
+    assert_that!(queue.retry_dead(&dead[0]).await.is_err(), eq(true));
+    let job2 = queue.fetch::<Email>().await?;
+    assert_that!(job2.is_none(), eq(true));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_dead_letters_invalid_payload() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+    Queue::migrate(&db).await?;
+
+    let queue = Queue::new(&db, "emails");
+    queue.enqueue(&json!({ "from": "linh1@gmail.com" })).await?;
+    queue
+        .enqueue(&Email {
+            to: "linh2@gmail.com".to_string(),
+        })
+        .await?;
+    queue.enqueue(&"not an email").await?;
+
+    // the other jobs can't be an `Email`, they're dead-lettered on the way
+    let job = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(job.payload.to, eq("linh2@gmail.com"));
+    queue.complete(&job).await?;
+    assert_that!(queue.fetch::<Email>().await?.is_none(), eq(true));
+
+    let dead = queue.dead_letters().await?;
+    assert_that!(
+        dead.iter().map(|job| &job.payload).collect::<Vec<_>>(),
+        elements_are![
+            eq(&json!({ "from": "linh1@gmail.com" })),
+            eq(&json!("not an email"))
+        ]
+    );
+    assert_that!(dead[0].last_error, some(starts_with("invalid payload")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_visibility_timeout() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+    Queue::migrate(&db).await?;
+
+    let queue = Queue::new(&db, "emails").with_options(
+        QueueOptions::default()
+            .max_attempts(2)
+            .visibility_timeout(Duration::ZERO),
+    );
+    let id = queue
+        .enqueue(&Email {
+            to: "linh1@gmail.com".to_string(),
+        })
+        .await?;
+
+    // the worker holding it never reports back, so it is handed out again
+    let stale = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(stale.attempts, eq(1));
+    let job = queue.fetch::<Email>().await?.unwrap();
+    assert_that!(job.id, eq(id));
+    assert_that!(job.attempts, eq(2));
+
+    // and the first worker can no longer complete or fail it
+    for result in [
+        queue.complete(&stale).await,
+        queue.fail(&stale, "too late").await,
+    ] {
+        assert_that!(
+            result.unwrap_err().downcast_ref::<LostClaim>(),
+            some(eq(&LostClaim { id, attempts: 1 }))
+        );
+    }
+
+    assert_that!(queue.fetch::<Email>().await?.is_none(), eq(true));
+    let dead = queue.dead_letters().await?;
+    assert_that!(dead[0].last_error, some(eq("visibility timeout expired")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_worker_wakes_up_on_notify() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+    Queue::migrate(&db).await?;
+
+    // polling alone would only pick the job up after a minute
+    let queue = Queue::new(&db, "emails")
+        .with_options(QueueOptions::default().poll_interval(Duration::from_secs(60)));
+    let sent = RefCell::new(vec![]);
+
+    let worker = tokio::time::timeout(
+        Duration::from_secs(3),
+        queue.work(|job: Job<Email>| {
+            let sent = &sent;
+            async move {
+                sent.borrow_mut().push(job.payload.to);
+                Ok(())
+            }
+        }),
+    );
+    let producer = async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        queue
+            .enqueue(&Email {
+                to: "linh1@gmail.com".to_string(),
+            })
+            .await
+    };
+
+    let (worker, id) = tokio::join!(worker, producer);
+    assert_that!(worker.is_err(), eq(true));
+    id?;
+    assert_that!(sent.into_inner(), elements_are![eq("linh1@gmail.com")]);
+    assert_that!(queue.fetch::<Email>().await?.is_none(), eq(true));
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::prelude::*;
+use dojo_orm::{Database, Model};
+
+mod common;
+
+#[tokio::test]
+async fn test_raw_query_with_params() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let id = Uuid::new_v4();
+    let name = "linh1".to_string();
+    let email = "linh1@gmail.com".to_string();
+    let affected = db
+        .raw_execute(sql!(
+            "INSERT INTO users (id, name, email) VALUES ($1, $2, $3)",
+            &id,
+            &name,
+            &email
+        ))
+        .await?;
+    assert_that!(affected, eq(1));
+
+    let query = RawSql::new("SELECT id, name, email, created_at, updated_at FROM users WHERE ")
+        .append(sql!("name = $1", &name))
+        .push(" AND ")
+        .append(sql!("email = $1", &email));
+    let rows = db.raw_query(query).await?;
+    let users = rows
+        .into_iter()
+        .map(User::from_row)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    assert_that!(
+        users,
+        elements_are![pat!(User {
+            id: eq(id),
+            name: eq("linh1".to_string()),
+            email: eq("linh1@gmail.com".to_string()),
+            created_at: anything(),
+            updated_at: anything(),
+        })]
+    );
+
+    let user = db
+        .bind::<User>()
+        .where_by(raw(sql!("lower(name) = lower($1)", &"LINH1")))
+        .first()
+        .await?;
+    assert_that!(
+        user,
+        some(pat!(User {
+            id: eq(id),
+            name: eq("linh1".to_string()),
+            email: eq("linh1@gmail.com".to_string()),
+            created_at: anything(),
+            updated_at: anything(),
+        }))
+    );
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use chrono::{NaiveDateTime, Utc};
 use common::*;
 use dojo_macros::Model;
 use dojo_orm::predicates::*;
+use dojo_orm::sql;
 use dojo_orm::Database;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -39,9 +40,10 @@ async fn test_raw_text_search() -> anyhow::Result<()> {
 
     let user = db
         .bind::<Movie>()
-        .where_by(raw_str(
-            "detail @@ websearch_to_tsquery('english', 'most powerful Mafia')",
-        ))
+        .where_by(raw(sql!(
+            "detail @@ websearch_to_tsquery('english', $1)",
+            &"most powerful Mafia"
+        )))
         .first()
         .await?;
 
@@ -81,11 +83,19 @@ async fn test_text_search() -> anyhow::Result<()> {
 
     let user = db
         .bind::<Movie>()
-        .where_by(text_search("detail", "english", "powerful Mafia clan"))
+        .where_by(text_search("detail", &"english", &"powerful Mafia clan"))
         .first()
         .await?;
 
     println!("user: {:?}", user);
 
+    // the search is bound, so quotes in it are plain text
+    let user = db
+        .bind::<Movie>()
+        .where_by(text_search("detail", &"english", &"patriarch's' clan"))
+        .first()
+        .await?;
+    assert!(user.is_some());
+
     Ok(())
 }
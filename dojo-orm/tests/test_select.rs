@@ -1,12 +1,14 @@
 use chrono::{NaiveDateTime, Utc};
+use futures::TryStreamExt;
 use googletest::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use common::*;
 use dojo_macros::Model;
-use dojo_orm::predicates::*;
-use dojo_orm::Database;
+use dojo_orm::pagination::CursorOptions;
+use dojo_orm::prelude::*;
+use dojo_orm::{Database, MaxRows};
 
 mod common;
 
@@ -62,6 +64,11 @@ async fn test_select_with_limit() -> anyhow::Result<()> {
         ]
     );
 
+    // awaiting the select is Send, e.g. inside a spawned task or a handler
+    let db = db.clone();
+    let users = tokio::spawn(async move { db.bind::<User>().limit(2).await }).await??;
+    assert_that!(users, len(eq(2)));
+
     Ok(())
 }
 
@@ -100,3 +107,251 @@ async fn test_select_with_where() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_select_with_typed_columns() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3");
+
+    let email = "linh3@gmail.com".to_string();
+    let names = vec!["linh1", "linh3"];
+    let users = db
+        .bind::<User>()
+        .where_by(in_list(User::NAME_COL, &names))
+        .where_by(equals(User::EMAIL_COL, &email))
+        .order_by(desc(User::CREATED_AT_COL))
+        .await?;
+    assert_that!(
+        users,
+        elements_are![pat!(User {
+            id: anything(),
+            name: eq("linh3".to_string()),
+            email: eq("linh3@gmail.com".to_string()),
+            created_at: anything(),
+            updated_at: anything(),
+        })]
+    );
+
+    let user = db
+        .bind::<User>()
+        .where_by(equals(User::NAME_COL, &"linh2"))
+        .first()
+        .await?;
+    assert_that!(user.map(|user| user.name), some(eq("linh2")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_all_without_limit() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let users = (0..600)
+        .map(|i| User {
+            id: Uuid::new_v4(),
+            name: format!("linh{}", i),
+            email: format!("linh{}@gmail.com", i),
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        })
+        .collect::<Vec<_>>();
+    let inputs = users.iter().collect::<Vec<_>>();
+    db.insert(&inputs).all().await?;
+
+    let users = db.bind::<User>().all().await?;
+    assert_that!(users, len(eq(600)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_stream() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3", "linh4", "linh5");
+
+    let users = db
+        .bind::<User>()
+        .where_by(in_list("name", &vec!["linh1", "linh3", "linh5"]))
+        .order_by(asc("name"))
+        .fetch_size(2)
+        .stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_that!(
+        users,
+        elements_are![
+            pat!(User {
+                id: anything(),
+                name: eq("linh1".to_string()),
+                email: eq("linh1@gmail.com".to_string()),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+            pat!(User {
+                id: anything(),
+                name: eq("linh3".to_string()),
+                email: eq("linh3@gmail.com".to_string()),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+            pat!(User {
+                id: anything(),
+                name: eq("linh5".to_string()),
+                email: eq("linh5@gmail.com".to_string()),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+        ]
+    );
+
+    // the stream is Send, so it can be consumed on another task
+    let db = db.clone();
+    let users = tokio::spawn(async move {
+        let mut select = db.bind::<User>();
+        let select = select.fetch_size(2);
+        select.stream().try_collect::<Vec<_>>().await
+    })
+    .await??;
+    assert_that!(users, len(eq(5)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_with_offset() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3", "linh4");
+
+    let users = db
+        .bind::<User>()
+        .order_by(asc("name"))
+        .limit(2)
+        .offset(1)
+        .await?;
+    assert_that!(
+        users,
+        elements_are![
+            pat!(User {
+                id: anything(),
+                name: eq("linh2".to_string()),
+                email: eq("linh2@gmail.com".to_string()),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+            pat!(User {
+                id: anything(),
+                name: eq("linh3".to_string()),
+                email: eq("linh3@gmail.com".to_string()),
+                created_at: anything(),
+                updated_at: anything(),
+            }),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_max_rows_guard() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    create_users!(db, names = "linh1", "linh2", "linh3");
+
+    let guarded = db.clone().with_max_rows(MaxRows::Error(2));
+    let result = guarded.bind::<User>().all().await;
+    assert_that!(result.is_err(), eq(true));
+
+    let users = guarded.bind::<User>().limit(2).await?;
+    assert_that!(users, len(eq(2)));
+
+    let guarded = db.clone().with_max_rows(MaxRows::Warn(2));
+    let users = guarded.bind::<User>().all().await?;
+    assert_that!(users, len(eq(2)));
+
+    let guarded = db.clone().with_max_rows(MaxRows::Error(3));
+    let users = guarded.bind::<User>().all().await?;
+    assert_that!(users, len(eq(3)));
+
+    // the default page size is guarded like any other
+    let result = guarded.bind::<User>().cursor(None, None, None, None).await;
+    assert_that!(result.is_err(), eq(true));
+    let page = guarded
+        .bind::<User>()
+        .cursor_with_options(
+            None,
+            None,
+            None,
+            None,
+            CursorOptions::default().page_size(2),
+        )
+        .await?;
+    assert_that!(page.items, len(eq(2)));
+    assert_that!(page.has_next, eq(true));
+
+    let guarded = db.clone().with_max_rows(MaxRows::Warn(2));
+    let page = guarded
+        .bind::<User>()
+        .cursor(None, None, None, None)
+        .await?;
+    assert_that!(page.items, len(eq(2)));
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::predicates::{
+    compare_subquery, equals, exists, in_subquery, not_exists, not_in_subquery, raw,
+};
+use dojo_orm::Database;
+
+mod common;
+
+#[tokio::test]
+async fn test_subquery() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "archived_users", sort_keys = ["created_at", "id"])]
+    struct ArchivedUser {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        archived_at: NaiveDateTime,
+    }
+
+    let now = Utc::now().naive_utc();
+    let users = (1..=3)
+        .map(|i| User {
+            id: Uuid::new_v4(),
+            name: format!("linh{}", i),
+            email: format!("linh{}@gmail.com", i),
+            created_at: now + Duration::seconds(i),
+            updated_at: now,
+        })
+        .collect::<Vec<_>>();
+    db.insert(&users.iter().collect::<Vec<_>>()).all().await?;
+
+    // linh1 and linh2 are archived, only linh2 under its own name
+    let archived = [(&users[0], "old"), (&users[1], "linh2")].map(|(user, name)| ArchivedUser {
+        id: user.id,
+        name: name.to_string(),
+        email: user.email.clone(),
+        created_at: user.created_at,
+        archived_at: now,
+    });
+    db.insert(&[&archived[0], &archived[1]]).all().await?;
+
+    let names = |users: Vec<User>| {
+        let mut names = users.into_iter().map(|user| user.name).collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let found = db
+        .bind::<User>()
+        .where_by(exists(
+            db.bind::<ArchivedUser>()
+                .where_by(raw("archived_users.id = users.id"))
+                .where_by(raw("archived_users.name = users.name")),
+        )?)
+        .all()
+        .await?;
+    assert_that!(names(found), elements_are![eq("linh2")]);
+
+    let found = db
+        .bind::<User>()
+        .where_by(not_exists(
+            db.bind::<ArchivedUser>()
+                .where_by(raw("archived_users.id = users.id")),
+        )?)
+        .all()
+        .await?;
+    assert_that!(names(found), elements_are![eq("linh3")]);
+
+    // the subquery's params follow the outer ones
+    let found = db
+        .bind::<User>()
+        .where_by(equals("email", &"linh1@gmail.com"))
+        .where_by(in_subquery(
+            "id",
+            db.bind::<ArchivedUser>()
+                .where_by(equals("name", &"old"))
+                .subquery(&["id"])?,
+        ))
+        .all()
+        .await?;
+    assert_that!(names(found), elements_are![eq("linh1")]);
+
+    let found = db
+        .bind::<User>()
+        .where_by(not_in_subquery(
+            "id",
+            db.bind::<ArchivedUser>()
+                .where_by(equals("name", &"old"))
+                .subquery(&["id"])?,
+        ))
+        .all()
+        .await?;
+    assert_that!(names(found), elements_are![eq("linh2"), eq("linh3")]);
+
+    let found = db
+        .bind::<User>()
+        .where_by(compare_subquery(
+            "created_at",
+            "=",
+            db.bind::<User>().subquery(&["max(created_at)"])?,
+        ))
+        .all()
+        .await?;
+    assert_that!(names(found), elements_are![eq("linh3")]);
+
+    Ok(())
+}
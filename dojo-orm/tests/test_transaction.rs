@@ -0,0 +1,120 @@
+use chrono::{NaiveDateTime, Utc};
+use futures::TryStreamExt;
+use googletest::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use common::*;
+use dojo_macros::Model;
+use dojo_orm::Database;
+
+mod common;
+
+macro_rules! create_users {
+    ($db: ident, names = $($name:literal),+) => {
+        $db.insert(&[
+            $(&User {
+                id: Uuid::new_v4(),
+                name: $name.to_string(),
+                email: concat!($name, "@gmail.com").to_string(),
+                created_at: Utc::now().naive_utc(),
+                updated_at: Utc::now().naive_utc(),
+            }),+
+        ]).all().await?;
+    };
+}
+
+#[tokio::test]
+async fn test_transaction_commit_and_rollback() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let tx = db.begin().await?;
+    create_users!(tx, names = "linh1");
+
+    // the page and its count both see the uncommitted row
+    let pagination = tx.bind::<User>().cursor(Some(10), None, None, None).await?;
+    assert_that!(pagination.items.len(), eq(1));
+    assert_that!(pagination.total_nodes, some(eq(1)));
+    assert_that!(db.bind::<User>().count().await?, eq(0));
+
+    let users = tx.bind::<User>().stream().try_collect::<Vec<_>>().await?;
+    assert_that!(users.len(), eq(1));
+
+    tx.commit().await?;
+    assert_that!(db.bind::<User>().count().await?, eq(1));
+
+    let tx = db.begin().await?;
+    create_users!(tx, names = "linh2");
+    tx.rollback().await?;
+    assert_that!(db.bind::<User>().count().await?, eq(1));
+
+    {
+        let tx = db.begin().await?;
+        create_users!(tx, names = "linh3");
+    }
+    // the rollback of a dropped transaction runs in the background
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_that!(db.bind::<User>().count().await?, eq(1));
+
+    // dropped off the runtime, the rollback still runs on it
+    let tx = db.begin().await?;
+    create_users!(tx, names = "linh4");
+    std::thread::spawn(move || drop(tx)).join().unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_that!(db.bind::<User>().count().await?, eq(1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transaction_stream_fetch_size() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "users", sort_keys = ["created_at", "id"])]
+    struct User {
+        id: Uuid,
+        name: String,
+        email: String,
+        created_at: NaiveDateTime,
+        updated_at: NaiveDateTime,
+    }
+
+    let tx = db.begin().await?;
+    create_users!(tx, names = "linh1", "linh2", "linh3", "linh4", "linh5");
+
+    // the rows come from a cursor in batches of two, the last one short
+    let users = tx
+        .bind::<User>()
+        .fetch_size(2)
+        .stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_that!(users.len(), eq(5));
+
+    // a second stream declares its own cursor on the same connection
+    let users = tx
+        .bind::<User>()
+        .fetch_size(5)
+        .stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_that!(users.len(), eq(5));
+
+    tx.commit().await?;
+    assert_that!(db.bind::<User>().count().await?, eq(5));
+
+    Ok(())
+}
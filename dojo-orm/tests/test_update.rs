@@ -134,3 +134,81 @@ async fn test_update_embedded() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_update_jsonb() -> anyhow::Result<()> {
+    let db: Database;
+    setup!(db);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, EmbeddedModel)]
+    struct ProductDetail {
+        manufacturer: String,
+        color: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Model)]
+    #[dojo(name = "products", sort_keys = ["created_at", "id"])]
+    struct Product {
+        id: Uuid,
+        name: String,
+        detail: Option<ProductDetail>,
+        price: Option<i32>,
+        created_at: NaiveDateTime,
+    }
+
+    #[derive(UpdateModel, Debug)]
+    struct UpdateProduct {
+        name: Option<String>,
+    }
+
+    let id = Uuid::new_v4();
+    db.insert(&[&Product {
+        id,
+        name: "product 1".to_string(),
+        detail: Some(ProductDetail {
+            manufacturer: "Company A".to_string(),
+            color: Some("red".to_string()),
+            tags: vec!["new".to_string()],
+        }),
+        price: None,
+        created_at: Utc::now().naive_utc(),
+    }])
+    .all()
+    .await?;
+
+    let manufacturer = serde_json::json!("Company B");
+    let patch = serde_json::json!({ "released": true });
+    let tag = serde_json::json!("sale");
+    let product = db
+        .update::<Product, UpdateProduct>(&UpdateProduct {
+            name: Some("product 2".to_string()),
+        })
+        .jsonb_set("detail", &["manufacturer"], &manufacturer)
+        .jsonb_merge("detail", &patch)
+        .jsonb_remove("detail", &["color"])
+        .jsonb_array_append("detail", &["tags"], &tag)
+        .where_by(equals("id", &id))
+        .exec()
+        .await?;
+
+    assert_that!(product.name, eq("product 2"));
+    assert_that!(
+        product.detail,
+        some(eq(ProductDetail {
+            manufacturer: "Company B".to_string(),
+            color: None,
+            tags: vec!["new".to_string(), "sale".to_string()],
+        }))
+    );
+
+    let rows = db
+        .raw_query("SELECT detail->'released' AS released FROM products")
+        .await?;
+    assert_that!(
+        rows[0].get::<_, serde_json::Value>("released"),
+        eq(serde_json::json!(true))
+    );
+
+    Ok(())
+}